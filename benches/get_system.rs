@@ -0,0 +1,157 @@
+//! Sampling a synthetic `/proc`, so that the results don't depend on what the machine
+//! running the benchmark happens to be running.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use fell::Proc;
+
+const PROCESSES: i32 = 300;
+const THREADS_PER_PROCESS: i32 = 4;
+/// Every this many processes one is a kernel thread
+const KERNEL_THREAD_EVERY: i32 = 5;
+
+/// `PF_KTHREAD` in the flags field of `stat`
+const PF_KTHREAD: u32 = 0x0020_0000;
+/// `PF_RANDOMIZE`, which most user processes have
+const PF_RANDOMIZE: u32 = 0x0040_0000;
+
+/// A generated proc root, removed when dropped
+struct Fixture {
+    root: PathBuf,
+}
+
+impl Fixture {
+    fn new() -> io::Result<Self> {
+        let root = std::env::temp_dir().join(format!("fell-bench-root-{}", std::process::id()));
+        // Left over if a previous run was interrupted
+        let _ = fs::remove_dir_all(&root);
+        write_root(&root)?;
+        for pid in 1..=PROCESSES {
+            write_process(&root, pid)?;
+        }
+
+        Ok(Fixture { root })
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Write the system wide files
+fn write_root(root: &Path) -> io::Result<()> {
+    fs::create_dir_all(root)?;
+    fs::write(root.join("uptime"), "1234.56 4321.00\n")?;
+    fs::write(root.join("loadavg"), "0.50 0.25 0.10 1/100 42\n")?;
+    let mut stat = String::from("cpu  400 0 400 4000 0 0 0 0 0 0\n");
+    for cpu in 0..4 {
+        stat.push_str(&format!("cpu{cpu} 100 0 100 1000 0 0 0 0 0 0\n"));
+    }
+    stat.push_str("intr 1000\nctxt 2000\nprocesses 300\n");
+    fs::write(root.join("stat"), stat)?;
+    fs::write(
+        root.join("meminfo"),
+        "MemTotal: 8388608 kB\nMemAvailable: 4194304 kB\nSwapTotal: 0 kB\nSwapFree: 0 kB\n",
+    )?;
+    fs::write(root.join("vmstat"), "pswpin 0\npswpout 0\n")?;
+
+    Ok(())
+}
+
+/// Write process `pid`, with its threads unless it is a kernel thread
+fn write_process(root: &Path, pid: i32) -> io::Result<()> {
+    let dir = root.join(pid.to_string());
+    let kernel_thread = pid % KERNEL_THREAD_EVERY == 0;
+    let (name, flags, cmdline, threads) = if kernel_thread {
+        (format!("kworker/{pid}"), PF_KTHREAD, String::new(), 1)
+    } else {
+        (
+            format!("proc-{pid}"),
+            PF_RANDOMIZE,
+            format!("/usr/bin/proc-{pid}\0--flag\0value\0"),
+            THREADS_PER_PROCESS,
+        )
+    };
+
+    write_task(&dir, pid, pid, &name, flags, &cmdline, threads)?;
+    fs::write(
+        dir.join("status"),
+        format!("Name:\t{name}\nUid:\t0\t0\t0\t0\nCapEff:\t0000000000000000\n"),
+    )?;
+    fs::write(dir.join("cgroup"), "0::/system.slice/bench.service\n")?;
+
+    for tid in 0..threads {
+        // The main thread has the pid as its id
+        let tid = if tid == 0 {
+            pid
+        } else {
+            PROCESSES + pid * THREADS_PER_PROCESS + tid
+        };
+        write_task(
+            &dir.join("task").join(tid.to_string()),
+            tid,
+            pid,
+            &name,
+            flags,
+            &cmdline,
+            threads,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_task(
+    dir: &Path,
+    tid: i32,
+    pid: i32,
+    name: &str,
+    flags: u32,
+    cmdline: &str,
+    threads: i32,
+) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(
+        dir.join("stat"),
+        format!(
+            "{tid} ({name}) S 1 {pid} {pid} 0 -1 {flags} 120 0 3 0 {utime} 5 0 0 20 0 {threads} 0 \
+             100 104857600 2048 18446744073709551615 0 0 0 0 0 0 0 0 0 0 0 0 17 {cpu} 0 0 0 0 \
+             0 0 0 0 0 0 0 0 0\n",
+            utime = tid * 7,
+            cpu = tid % 4,
+        ),
+    )?;
+    fs::write(dir.join("cmdline"), cmdline)?;
+
+    Ok(())
+}
+
+fn get_system(c: &mut Criterion) {
+    let fixture = Fixture::new().expect("Failed to write the proc fixture");
+    let mut group = c.benchmark_group("get_system");
+
+    for (id, get_threads) in [("processes", false), ("threads", true)] {
+        let mut proc = Proc::new().root(&fixture.root);
+        // Later samples also calculate usage from the previous one
+        let system = proc.get_system(get_threads).expect("Failed to sample");
+        assert!(
+            system.errors.is_empty(),
+            "The fixture can't be read: {:?}",
+            system.errors
+        );
+        group.bench_function(id, |b| {
+            b.iter(|| proc.get_system(get_threads).expect("Failed to sample"))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, get_system);
+criterion_main!(benches);
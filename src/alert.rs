@@ -0,0 +1,96 @@
+use serde::Deserialize;
+
+use crate::proc::System;
+
+/// Thresholds that flash the screen or ring the bell when crossed
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Alerts {
+    /// Alert when the CPU usage across all CPUs goes above this percentage
+    pub cpu: Option<f32>,
+    /// Alert when the memory usage goes above this percentage
+    pub memory: Option<f32>,
+    /// Alert when a process with a given name uses too much CPU
+    pub processes: Vec<ProcessAlert>,
+    /// Ring the terminal bell as well as flashing the info bar
+    pub bell: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProcessAlert {
+    pub name: String,
+    /// CPU usage percentage of a single process above which to alert
+    pub cpu: f32,
+}
+
+impl Alerts {
+    /// Whether any of the thresholds is crossed in `system`
+    pub fn triggered(&self, system: &System) -> bool {
+        let cpu = system.cpu_usage.as_ref().map(|usage| usage.total);
+        if cpu.zip(self.cpu).is_some_and(|(cpu, limit)| cpu > limit) {
+            return true;
+        }
+
+        let mem_usage = &system.mem_usage;
+        if mem_usage.mem_total > 0 {
+            let memory = mem_usage.mem_used() as f32 * 100.0 / mem_usage.mem_total as f32;
+            if self.memory.is_some_and(|limit| memory > limit) {
+                return true;
+            }
+        }
+
+        self.processes.iter().any(|alert| {
+            system
+                .processes
+                .iter()
+                .any(|p| p.name == alert.name && p.cpu_usage.is_some_and(|cpu| cpu > alert.cpu))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fell::{MemInfo, ProcessInfo};
+
+    use super::*;
+
+    #[test]
+    fn triggered_by_memory_and_named_process() {
+        let system = System {
+            processes: vec![ProcessInfo {
+                pid: 1,
+                name: "backup".to_string(),
+                cpu_usage: Some(80.0),
+                ..Default::default()
+            }],
+            mem_usage: MemInfo {
+                mem_total: 1000,
+                mem_free: 400,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert!(!Alerts::default().triggered(&system));
+        assert!(Alerts {
+            memory: Some(50.0),
+            ..Default::default()
+        }
+        .triggered(&system));
+        assert!(!Alerts {
+            memory: Some(70.0),
+            ..Default::default()
+        }
+        .triggered(&system));
+
+        let process = |name: &str| Alerts {
+            processes: vec![ProcessAlert {
+                name: name.to_string(),
+                cpu: 50.0,
+            }],
+            ..Default::default()
+        };
+        assert!(process("backup").triggered(&system));
+        assert!(!process("cron").triggered(&system));
+    }
+}
@@ -1,24 +1,152 @@
-use std::{io, sync::mpsc};
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+    sync::mpsc,
+    time::{Duration, Instant},
+};
 
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
 use ratatui::{
-    layout::{Constraint, Layout},
-    widgets::Widget,
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Paragraph, StatefulWidget, TableState, Widget, Wrap},
     Frame,
 };
+use rustix::process::Signal;
 
 use crate::{
-    cpu_info_widget::CpuInfoWidget, proc::System, process_list::ProcessList,
-    system_info_widget::SystemInfoWidget, tui::Tui, Message,
+    alert::Alerts,
+    column::Column,
+    core_history_widget::CoreHistoryWidget,
+    cpu_info_widget::CpuInfoWidget,
+    error_log::{ErrorLog, ErrorLogWidget},
+    gpu_widget::GpuWidget,
+    group_list::{GroupBy, GroupList, GroupSortKey},
+    history::{History, DEFAULT_HISTORY_DURATION},
+    meter_bar::MeterBar,
+    proc::{
+        details::ProcessDetails,
+        process_info::{ProcessInfo, ProcessType},
+        state::State,
+        System,
+    },
+    process_detail_widget::ProcessDetailWidget,
+    process_list::{thread_cpu_totals, NameMode, ProcessList, DEFAULT_MAX_NAME_WIDTH},
+    sort_key::{Key, SortKey, SortSpec},
+    sysinfo_thread,
+    system_info_widget::{Peaks, SystemInfoWidget},
+    theme::{self, severity_style, Theme},
+    tui::Tui,
+    utils::{human_bytes_fixed_unit, send_signal, MemoryUnit, Precision},
+    Message,
 };
 
+/// How long the info bar is flashed for when an alert fires
+const FLASH_DURATION: Duration = Duration::from_secs(1);
+/// How many samples the row of a process that just changed state is flashed for
+const STATE_FLASH_SAMPLES: u8 = 2;
+/// Most lines the command of the selected process wraps over below the process list
+const MAX_COMMAND_LINES: u16 = 3;
+
+/// Smallest terminal the layout is drawn in, below it only a message is shown
+const MIN_WIDTH: u16 = 20;
+const MIN_HEIGHT: u16 = 10;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+enum InputMode {
+    #[default]
+    Normal,
+    Search,
+    Filter,
+    EnvFilter,
+}
+
 #[derive(Debug, Default)]
 pub struct App {
     exit: bool,
+    confirm_quit: bool,
+    /// Waiting for the user to confirm quitting
+    quit_pending: bool,
     stopped: bool,
     show_kernel_threads: bool,
+    privileged_only: bool,
     show_threads: bool,
+    collapse_threads: bool,
+    tree: bool,
+    name_mode: NameMode,
+    max_name_width: u16,
+    memory_unit: MemoryUnit,
+    precision: Precision,
+    cpu_bars: bool,
+    show_cores: bool,
+    /// Order the cores by usage instead of by number
+    cores_busiest_first: bool,
+    columns: Vec<Column>,
+    sort: SortSpec,
+    theme: Theme,
     current_data: System,
+    peaks: Peaks,
+    show_peaks: bool,
+    /// Samples kept by each history, covering the configured history duration
+    history_len: usize,
+    load_history: History<f32>,
+    /// Usage of all cores together
+    cpu_history: History<f32>,
+    /// Process kept selected and shown in the status line
+    watch_pid: Option<i32>,
+    /// Quit with this exit code once the watched process exits
+    watch_exit_code: Option<i32>,
+    /// The watched process has exited
+    watch_gone: bool,
+    exit_code: Option<i32>,
+    keep_frame: bool,
+    last_frame: Option<Buffer>,
+    /// Usage of each core, keyed by the CPU number
+    core_history: HashMap<u32, History<f32>>,
+    /// Index of the core chosen in the CPU panel
+    core_focus: Option<usize>,
+    /// Show the history of the chosen core instead of the process list
+    zoom_core: bool,
+    alerts: Alerts,
+    alerting: bool,
+    flash_until: Option<Instant>,
+    table_state: TableState,
+    page_size: u16,
+    input_mode: InputMode,
+    show_details: bool,
+    /// Show the full command of the selected process under the list
+    show_command: bool,
+    errors: ErrorLog,
+    show_errors: bool,
+    /// Lines the error log is scrolled down by
+    errors_scroll: u16,
+    /// Show one row per group of processes instead of the process list
+    group_by: Option<GroupBy>,
+    /// Sort of the grouped views, which have other columns than the process list. It is
+    /// kept apart from `sort`, so switching between the process list and a grouped view
+    /// keeps the order of both, and all groupings share it.
+    group_sort: SortSpec<GroupSortKey>,
+    group_table_state: TableState,
+    /// Processes selected for the next action
+    marked: HashSet<i32>,
+    flash_state_changes: bool,
+    /// Samples left to flash each process that just entered a state needing attention,
+    /// keyed by pid
+    state_flashes: HashMap<i32, u8>,
+    search: String,
+    filter: String,
+    /// Show the processes not matching `filter`
+    invert_filter: bool,
+    env_filter: String,
+    env_matches: HashSet<i32>,
+    /// Whether the sysinfo thread was last told to read the environments
+    reading_environ: bool,
+    /// The process the sysinfo thread was last told to send the details of
+    details_pid: Option<i32>,
+    /// The latest details sent by the sysinfo thread and the process they belong to
+    details: Option<(i32, ProcessDetails)>,
 
     main_tx: Option<mpsc::Sender<Message>>,
 }
@@ -28,8 +156,141 @@ impl App {
         App {
             show_kernel_threads,
             show_threads,
+            max_name_width: DEFAULT_MAX_NAME_WIDTH,
+            show_cores: true,
+            flash_state_changes: true,
+            show_command: true,
+            // Start on the first row, so that the keys move from there
+            table_state: TableState::default().with_selected(0),
+            group_table_state: TableState::default().with_selected(0),
             ..Default::default()
         }
+        .history_duration(DEFAULT_HISTORY_DURATION)
+    }
+
+    /// How far back the history graphs go
+    pub fn history_duration(mut self, duration: Duration) -> Self {
+        self.history_len = History::<f32>::capacity_for(duration, sysinfo_thread::INTERVAL);
+        self.load_history.set_capacity(self.history_len);
+        self.cpu_history.set_capacity(self.history_len);
+        for history in self.core_history.values_mut() {
+            history.set_capacity(self.history_len);
+        }
+
+        self
+    }
+
+    pub fn columns(mut self, columns: Vec<Column>) -> Self {
+        self.columns = columns;
+
+        self
+    }
+
+    pub fn sort_by(mut self, sort: SortSpec) -> Self {
+        self.sort = sort;
+
+        self
+    }
+
+    pub fn filter(mut self, filter: String) -> Self {
+        self.filter = filter;
+
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+
+        self
+    }
+
+    pub fn alerts(mut self, alerts: Alerts) -> Self {
+        self.alerts = alerts;
+
+        self
+    }
+
+    pub fn name_mode(mut self, name_mode: NameMode) -> Self {
+        self.name_mode = name_mode;
+
+        self
+    }
+
+    /// Flash the rows of processes that become stuck or stop running, see
+    /// [`State::needs_attention`]
+    pub fn flash_state_changes(mut self, flash: bool) -> Self {
+        self.flash_state_changes = flash;
+
+        self
+    }
+
+    pub fn max_name_width(mut self, width: u16) -> Self {
+        self.max_name_width = width;
+
+        self
+    }
+
+    pub fn memory_unit(mut self, unit: MemoryUnit) -> Self {
+        self.memory_unit = unit;
+
+        self
+    }
+
+    /// Number of decimals of every percentage shown
+    pub fn precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+
+        self
+    }
+
+    pub fn show_cores(mut self, show: bool) -> Self {
+        self.show_cores = show;
+
+        self
+    }
+
+    /// Keep the process `pid` selected and show its usage in the status line. With an
+    /// `exit_code`, quit with that code once it exits.
+    pub fn watch_pid(mut self, pid: Option<i32>, exit_code: Option<i32>) -> Self {
+        self.watch_pid = pid;
+        self.watch_exit_code = exit_code;
+
+        self
+    }
+
+    /// Keep the last frame drawn before quitting, see [`App::last_frame`]
+    pub fn keep_frame(mut self, keep: bool) -> Self {
+        self.keep_frame = keep;
+
+        self
+    }
+
+    /// The frame shown when quitting, if enabled with [`App::keep_frame`]
+    pub fn last_frame(&self) -> Option<&Buffer> {
+        self.last_frame.as_ref()
+    }
+
+    /// What the Name column shows now, which can be changed with 'a' while running
+    pub fn current_name_mode(&self) -> NameMode {
+        self.name_mode
+    }
+
+    /// Exit code to quit with, set when quitting because the watched process exited
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// Show the processes as a tree, with the siblings sorted by the sort key
+    pub fn tree(mut self, tree: bool) -> Self {
+        self.tree = tree;
+
+        self
+    }
+
+    pub fn confirm_quit(mut self, confirm_quit: bool) -> Self {
+        self.confirm_quit = confirm_quit;
+
+        self
     }
 
     pub fn run(
@@ -39,26 +300,79 @@ impl App {
         main_tx: mpsc::Sender<Message>,
     ) -> io::Result<()> {
         let _ = main_tx.send(Message::SendThreads(self.show_threads));
+        let _ = main_tx.send(Message::ReadFds(self.columns.contains(&Column::Fd)));
+        let _ = main_tx.send(Message::ReadGpuMemory(
+            self.columns.contains(&Column::GpuMem),
+        ));
+        let _ = main_tx.send(Message::ReadSwap(self.columns.contains(&Column::Swap)));
+        let _ = main_tx.send(Message::ReadCaps(self.needs_caps()));
+        let _ = main_tx.send(Message::ReadIo(
+            self.columns.iter().any(|c| Column::IO.contains(c)),
+        ));
         self.main_tx = Some(main_tx);
 
         while !self.exit {
             terminal.draw(|frame| self.render_frame(frame))?;
 
             match thread_rx.recv() {
-                Ok(msg) => match msg {
-                    Message::SysInfo(system) => self.handle_msg(system),
-                    Message::Event(event) => self.handle_event(event),
-                    _ => {}
-                },
+                Ok(msg) => self.handle_message(msg),
                 Err(_) => break,
             }
+
+            // Handle everything that queued up while drawing before drawing again, so
+            // bursts of key presses only cost one redraw.
+            while let Ok(msg) = thread_rx.try_recv() {
+                self.handle_message(msg);
+            }
+            self.request_reads();
+        }
+
+        if self.keep_frame {
+            // Drawn again so that it doesn't show e.g. the quit confirmation
+            let frame = terminal.draw(|frame| self.render_frame(frame))?;
+            self.last_frame = Some(frame.buffer.clone());
         }
 
         Ok(())
     }
 
+    fn handle_message(&mut self, msg: Message) {
+        match msg {
+            Message::SysInfo(system) => self.handle_msg(system),
+            Message::Error(err) => self.errors.push(&err),
+            Message::Event(event) => self.handle_event(event),
+            Message::Details(pid, details) => self.details = Some((pid, details)),
+            _ => {}
+        }
+    }
+
+    /// Tell the sysinfo thread what it needs to read for the env filter and the details,
+    /// which change with nearly every key
+    fn request_reads(&mut self) {
+        let reading_environ = !self.env_filter.is_empty();
+        let details_pid = self
+            .selected_process()
+            .filter(|_| self.show_details)
+            .map(|p| p.pid);
+        let Some(tx) = &self.main_tx else {
+            return;
+        };
+
+        if reading_environ != self.reading_environ {
+            self.reading_environ = reading_environ;
+            let _ = tx.send(Message::ReadEnviron(reading_environ));
+        }
+        if details_pid != self.details_pid {
+            self.details_pid = details_pid;
+            let _ = tx.send(Message::WatchDetails(details_pid));
+        }
+    }
+
     fn render_frame(&mut self, frame: &mut Frame) {
-        frame.render_widget(self, frame.area());
+        frame.render_widget(&mut *self, frame.area());
+        if !self.theme.color {
+            theme::strip_colors(frame.buffer_mut());
+        }
     }
 
     fn handle_event(&mut self, event: Event) {
@@ -71,29 +385,566 @@ impl App {
     }
 
     fn handle_key_event(&mut self, key_event: KeyEvent) {
+        if self.quit_pending {
+            self.quit_pending = false;
+            self.exit = matches!(key_event.code, KeyCode::Char('y' | 'q'));
+            return;
+        }
+
+        if self.input_mode != InputMode::Normal {
+            self.handle_input_key_event(key_event);
+            return;
+        }
+
         match key_event.code {
+            KeyCode::Esc if self.show_errors => self.show_errors = false,
+            KeyCode::Esc if self.zoom_core => self.zoom_core = false,
+            KeyCode::Esc if self.core_focus.is_some() => self.core_focus = None,
+            KeyCode::Left | KeyCode::Up if self.core_focus.is_some() => self.move_core_focus(-1),
+            KeyCode::Right | KeyCode::Down if self.core_focus.is_some() => self.move_core_focus(1),
+            KeyCode::Enter if self.core_focus.is_some() => self.zoom_core = true,
+            KeyCode::Esc if self.show_details => self.show_details = false,
+            KeyCode::Esc if !self.search.is_empty() => self.search.clear(),
+            KeyCode::Esc if !self.filter.is_empty() => {
+                self.filter.clear();
+                self.invert_filter = false;
+            }
+            KeyCode::Esc if !self.env_filter.is_empty() => self.set_env_filter(String::new()),
+            KeyCode::Esc if !self.marked.is_empty() => self.marked.clear(),
             KeyCode::Char('q') | KeyCode::Esc => self.exit(),
             KeyCode::Char('k') => self.toggle_kernel_threads(),
+            KeyCode::Char('R') => self.toggle_privileged_only(),
             KeyCode::Char('t') => self.toggle_threads(),
-            KeyCode::Char('s') => self.toggle_stopped(),
+            KeyCode::Char('c') => self.toggle_collapse_threads(),
+            KeyCode::Char('p') => self.toggle_stopped(),
+            KeyCode::Char('z') => self.toggle_process_stopped(),
+            KeyCode::Char('b') => self.cpu_bars = !self.cpu_bars,
+            KeyCode::Char('C') => self.show_cores = !self.show_cores,
+            KeyCode::Char('B') => self.cores_busiest_first = !self.cores_busiest_first,
+            KeyCode::Char('Z') => self.toggle_core_focus(),
+            KeyCode::Char('T') => self.tree = !self.tree,
+            KeyCode::Char('L') => self.show_command = !self.show_command,
+            KeyCode::Char('a') => self.name_mode = self.name_mode.next(),
+            KeyCode::Char('E') => {
+                self.show_errors = !self.show_errors;
+                self.errors_scroll = 0;
+            }
+            KeyCode::Char('X') if self.show_errors => self.errors.clear(),
+            KeyCode::Down if self.show_errors => {
+                self.errors_scroll = self.errors_scroll.saturating_add(1)
+            }
+            KeyCode::Up if self.show_errors => {
+                self.errors_scroll = self.errors_scroll.saturating_sub(1)
+            }
+            KeyCode::Char('u') => {
+                self.group_by = match self.group_by {
+                    None => Some(GroupBy::User),
+                    Some(group_by) => group_by.next(),
+                };
+                self.group_table_state.select(Some(0));
+            }
+            KeyCode::Char('f') => self.toggle_fd_column(),
+            KeyCode::Char('i') => self.toggle_io_columns(),
+            KeyCode::Char('g') => self.toggle_gpu_memory_column(),
+            KeyCode::Char('w') => self.toggle_swap_column(),
+            KeyCode::Char('P') => self.show_peaks = !self.show_peaks,
+            KeyCode::Char('r') => self.peaks = Peaks::default(),
+            KeyCode::Char('>') if self.group_by.is_some() => {
+                self.group_sort = SortSpec::new(self.group_sort.key.next())
+            }
+            KeyCode::Char('<') if self.group_by.is_some() => {
+                self.group_sort = SortSpec::new(self.group_sort.key.previous())
+            }
+            KeyCode::Char('I') if self.group_by.is_some() => {
+                self.group_sort.direction = self.group_sort.direction.reversed()
+            }
+            KeyCode::Char('>') => self.set_sort(SortSpec::new(self.sort.key.next())),
+            KeyCode::Char('<') => self.set_sort(SortSpec::new(self.sort.key.previous())),
+            KeyCode::Char('I') => self.set_sort(SortSpec {
+                direction: self.sort.direction.reversed(),
+                ..self.sort
+            }),
+            KeyCode::Down => self.shown_table_state().select_next(),
+            KeyCode::Up => self.shown_table_state().select_previous(),
+            KeyCode::PageDown => {
+                let page_size = self.page_size;
+                self.shown_table_state().scroll_down_by(page_size)
+            }
+            KeyCode::PageUp => {
+                let page_size = self.page_size;
+                self.shown_table_state().scroll_up_by(page_size)
+            }
+            KeyCode::Home => self.shown_table_state().select_first(),
+            KeyCode::End => self.shown_table_state().select_last(),
+            KeyCode::Enter => self.show_details = self.table_state.selected().is_some(),
+            KeyCode::Char('/') => self.input_mode = InputMode::Search,
+            KeyCode::Char('\\') => self.input_mode = InputMode::Filter,
+            // Without a filter there is nothing to invert, or to show that it is inverted
+            KeyCode::Char('!') if !self.filter.is_empty() => {
+                self.invert_filter = !self.invert_filter
+            }
+            KeyCode::Char('e') => self.input_mode = InputMode::EnvFilter,
+            KeyCode::Char('n') => self.select_match(true),
+            KeyCode::Char('N') => self.select_match(false),
+            KeyCode::Char(' ') => self.toggle_mark(),
+            _ => {}
+        }
+    }
+
+    /// State of the table currently shown, the processes or the users
+    fn shown_table_state(&mut self) -> &mut TableState {
+        if self.group_by.is_some() {
+            &mut self.group_table_state
+        } else {
+            &mut self.table_state
+        }
+    }
+
+    fn handle_input_key_event(&mut self, key_event: KeyEvent) {
+        let input = match self.input_mode {
+            InputMode::Search => &mut self.search,
+            InputMode::EnvFilter => &mut self.env_filter,
+            _ => &mut self.filter,
+        };
+
+        match key_event.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                if self.input_mode == InputMode::Search {
+                    self.select_first_match();
+                }
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Esc => {
+                input.clear();
+                self.input_mode = InputMode::Normal;
+            }
             _ => {}
         }
+
+        if self.input_mode == InputMode::EnvFilter || key_event.code == KeyCode::Esc {
+            self.update_env_matches();
+        }
+        // A filter typed after this one was erased starts out not inverted
+        if self.filter.is_empty() {
+            self.invert_filter = false;
+        }
     }
 
+    fn set_env_filter(&mut self, env_filter: String) {
+        self.env_filter = env_filter;
+        self.update_env_matches();
+    }
+
+    /// Find the processes with an environment variable containing the env filter. The
+    /// environments are only read while the filter is in use, since reading them for
+    /// every process is expensive, so the first matches come with the next sample.
+    fn update_env_matches(&mut self) {
+        self.env_matches.clear();
+        if self.env_filter.is_empty() {
+            return;
+        }
+
+        for process in &self.current_data.processes {
+            // Other users' processes can't be read, so they never match
+            let mut environ = process.environ.iter().flatten();
+            if environ.any(|var| var.contains(&self.env_filter)) {
+                self.env_matches.insert(process.pid);
+            }
+        }
+    }
+
+    fn select_first_match(&mut self) {
+        let matches = self.process_list().search_matches();
+        let current = self.table_state.selected().unwrap_or_default();
+
+        if let Some(index) = matches.iter().find(|i| **i >= current).or(matches.first()) {
+            self.table_state.select(Some(*index));
+        }
+    }
+
+    fn select_match(&mut self, forward: bool) {
+        let matches = self.process_list().search_matches();
+        let current = self.table_state.selected();
+
+        let index = if forward {
+            matches
+                .iter()
+                .find(|i| current.is_none_or(|current| **i > current))
+                .or(matches.first())
+        } else {
+            matches
+                .iter()
+                .rev()
+                .find(|i| current.is_none_or(|current| **i < current))
+                .or(matches.last())
+        };
+
+        if let Some(index) = index {
+            self.table_state.select(Some(*index));
+        }
+    }
+
+    /// Usage of the watched process for the status line
+    fn watch_spans(&self, pid: i32) -> Vec<Span<'_>> {
+        let process = self.current_data.processes.iter().find(|p| p.pid == pid);
+        let Some(process) = process.filter(|_| !self.watch_gone) else {
+            return vec![format!("Watched {pid} exited").red().bold(), " | ".into()];
+        };
+
+        vec![
+            "Watching ".into(),
+            format!("{pid} {}", process.name).bold(),
+            " CPU: ".into(),
+            process
+                .cpu_usage
+                .map_or("-".to_string(), |cpu| self.precision.percent(cpu))
+                .bold(),
+            " Res: ".into(),
+            human_bytes_fixed_unit(process.memory, self.memory_unit, false).bold(),
+            " | ".into(),
+        ]
+    }
+
+    /// Summary of how the process list is currently shown
+    fn status_line(&self) -> Line<'_> {
+        let on_off = |on: bool| if on { "on" } else { "off" }.bold();
+        let mut spans = Vec::new();
+        if let Some(pid) = self.watch_pid {
+            spans.extend(self.watch_spans(pid));
+        }
+        let (header, direction) = match self.group_by {
+            Some(_) => (self.group_sort.key.header(), self.group_sort.direction),
+            None => (self.sort.key.header(), self.sort.direction),
+        };
+        spans.extend([
+            "Sort: ".into(),
+            format!("{header} {}", direction.arrow()).bold(),
+        ]);
+        if !self.filter.is_empty() {
+            let invert = if self.invert_filter { "!" } else { "" };
+            spans.extend([
+                " Filter: ".into(),
+                format!("{invert}{}", self.filter).bold(),
+            ]);
+        }
+        if !self.env_filter.is_empty() {
+            spans.extend([" Env: ".into(), self.env_filter.as_str().bold()]);
+        }
+        if let Some(group_by) = self.group_by {
+            spans.push(format!(" {}", group_by.title()).bold());
+        } else if self.tree {
+            spans.push(" Tree".bold());
+        }
+        if self.privileged_only {
+            spans.push(" Privileged only".bold());
+        }
+        if !self.marked.is_empty() {
+            spans.extend([" Marked: ".into(), self.marked.len().to_string().bold()]);
+        }
+        if !self.errors.is_empty() {
+            spans.extend([
+                " Errors: ".red(),
+                self.errors.len().to_string().red().bold(),
+            ]);
+        }
+        spans.extend([
+            " Threads: ".into(),
+            on_off(self.show_threads),
+            " Kernel threads: ".into(),
+            on_off(self.show_kernel_threads),
+            " Interval: ".into(),
+            format!("{:.1}s", sysinfo_thread::INTERVAL.as_secs_f32()).bold(),
+        ]);
+        if self.stopped {
+            spans.push(" PAUSED".bold().reversed());
+        }
+
+        Line::default().spans(spans)
+    }
+
+    fn process_list(&self) -> ProcessList<'_> {
+        ProcessList::new(&self.current_data, &self.theme)
+            .show_kernel_threads(self.show_kernel_threads)
+            .privileged_only(self.privileged_only)
+            .collapse_threads(self.show_threads && self.collapse_threads)
+            .tree(self.tree)
+            .search(&self.search)
+            .filter(&self.filter)
+            .invert_filter(self.invert_filter)
+            .env_matches((!self.env_filter.is_empty()).then_some(&self.env_matches))
+    }
+
+    fn selected_process(&self) -> Option<&ProcessInfo> {
+        let selected = self.table_state.selected()?;
+
+        self.process_list().visible_processes().nth(selected)
+    }
+
+    /// The full command of the selected process, wrapped to show what the Command column
+    /// cuts off
+    fn selected_command(&self) -> Option<Paragraph<'static>> {
+        if !self.show_command || self.group_by.is_some() || self.zoom_core {
+            return None;
+        }
+        let process = self.selected_process()?;
+        let command = if process.cmdline.is_empty() {
+            format!("[{}]", process.name)
+        } else {
+            process.quoted_cmdline()
+        };
+
+        Some(Paragraph::new(command).wrap(Wrap { trim: false }).italic())
+    }
+
+    /// Stop or continue the marked processes, or the selected process if none are marked
+    fn toggle_process_stopped(&mut self) {
+        let processes: Vec<&ProcessInfo> = if self.marked.is_empty() {
+            self.selected_process().into_iter().collect()
+        } else {
+            self.current_data
+                .processes
+                .iter()
+                .filter(|p| self.marked.contains(&p.pid))
+                .collect()
+        };
+
+        for process in processes {
+            let signal = if let State::Stopped = process.state {
+                Signal::Cont
+            } else {
+                Signal::Stop
+            };
+            let _ = send_signal(process.pid, signal);
+        }
+    }
+
+    /// Mark or unmark the selected process and move on to the next one
+    fn toggle_mark(&mut self) {
+        if let Some(pid) = self.selected_process().map(|p| p.pid) {
+            if !self.marked.remove(&pid) {
+                self.marked.insert(pid);
+            }
+            self.table_state.select_next();
+        }
+    }
+
+    /// Quit, unless there are marks or an open popup that would be lost, in which case
+    /// the user is asked first
     fn exit(&mut self) {
-        self.exit = true;
+        if self.confirm_quit && (!self.marked.is_empty() || self.show_details) {
+            self.quit_pending = true;
+        } else {
+            self.exit = true;
+        }
+    }
+
+    fn quit_prompt(&self) -> Line<'_> {
+        let question = match self.marked.len() {
+            0 => "Quit?".to_string(),
+            1 => "Quit and lose 1 marked process?".to_string(),
+            n => format!("Quit and lose {n} marked processes?"),
+        };
+
+        Line::default().spans([question.bold(), " (y/n)".into()])
     }
 
     fn toggle_stopped(&mut self) {
         self.stopped = !self.stopped;
+
+        if let Some(tx) = &self.main_tx {
+            let _ = tx.send(Message::Pause(self.stopped));
+        }
     }
 
-    fn handle_msg(&mut self, msg: System) {
+    fn handle_msg(&mut self, mut msg: System) {
+        for err in msg.errors.drain(..) {
+            self.errors.push(&err);
+        }
+        if let Some(pid) = self.watch_pid {
+            self.watch_gone = !msg.processes.iter().any(|p| p.pid == pid);
+            if self.watch_gone && self.watch_exit_code.is_some() {
+                self.exit_code = self.watch_exit_code;
+                self.exit = true;
+            }
+        }
         if !self.stopped {
-            self.current_data = msg;
+            self.update_state_flashes(&msg);
+            self.set_data(msg);
+            self.peaks.update(&self.current_data);
+            self.load_history.push(self.current_data.load_avg.one);
+            self.update_core_history();
+            self.check_alerts();
+        }
+    }
+
+    /// Count down the flashes of the processes that changed state earlier and start
+    /// flashing the ones that entered a state needing attention since the current sample
+    fn update_state_flashes(&mut self, new: &System) {
+        self.state_flashes.retain(|_, left| {
+            *left -= 1;
+            *left > 0
+        });
+        if !self.flash_state_changes {
+            return;
+        }
+
+        let states: HashMap<i32, &State> =
+            new.processes.iter().map(|p| (p.pid, &p.state)).collect();
+        for delta in new.diff(&self.current_data).changed {
+            if delta.prev_state.is_some() && states[&delta.pid].needs_attention() {
+                self.state_flashes.insert(delta.pid, STATE_FLASH_SAMPLES);
+            }
+        }
+    }
+
+    fn update_core_history(&mut self) {
+        let Some(cpu_usage) = &self.current_data.cpu_usage else {
+            return;
+        };
+        self.cpu_history.push(cpu_usage.total);
+        for (cpu, usage) in &cpu_usage.cores {
+            self.core_history
+                .entry(*cpu)
+                .or_insert_with(|| History::new(self.history_len))
+                .push(*usage);
+        }
+        if let Some(focus) = self.core_focus {
+            self.core_focus = Some(focus.min(cpu_usage.cores.len().saturating_sub(1)));
+        }
+    }
+
+    /// Choose the core `delta` cores away from the chosen one, wrapping around
+    fn move_core_focus(&mut self, delta: isize) {
+        let count = self
+            .current_data
+            .cpu_usage
+            .as_ref()
+            .map_or(0, |u| u.cores.len());
+        if let Some(focus) = self.core_focus.filter(|_| count > 0) {
+            self.core_focus = Some((focus as isize + delta).rem_euclid(count as isize) as usize);
+        }
+    }
+
+    /// Show a new sample. The selection follows the selected process to its new row, and
+    /// the scroll position is kept as long as there are still enough rows for it.
+    fn set_data(&mut self, data: System) {
+        let selected_pid = self
+            .watch_pid
+            .or_else(|| self.selected_process().map(|p| p.pid));
+
+        self.current_data = data;
+        let processes = &self.current_data.processes;
+        self.marked
+            .retain(|pid| processes.iter().any(|p| p.pid == *pid));
+        self.update_env_matches();
+        self.sort();
+
+        let visible: Vec<i32> = self
+            .process_list()
+            .visible_processes()
+            .map(|p| p.pid)
+            .collect();
+        let len = visible.len();
+        let selected = selected_pid.and_then(|pid| visible.iter().position(|p| *p == pid));
+        if selected.is_some() {
+            self.table_state.select(selected);
+        } else if let Some(i) = self.table_state.selected() {
+            self.table_state.select((len > 0).then(|| i.min(len - 1)));
+        }
+        let offset = self.table_state.offset_mut();
+        *offset = (*offset).min(len.saturating_sub(1));
+    }
+
+    /// Sum up the processes matching the filter, with a meter of their share of the memory
+    fn render_filter_totals(&self, area: Rect, buf: &mut Buffer) {
+        let list = self.process_list();
+        // Threads share the memory of their process, so only count each process once
+        let (count, memory) = list
+            .visible_processes()
+            .filter(|p| p.process_type != ProcessType::Thread)
+            .fold((0, 0), |(count, memory), p| (count + 1, memory + p.memory));
+        let mem_total = self.current_data.mem_usage.mem_total;
+        let percent = if mem_total > 0 {
+            memory as f32 * 100.0 / mem_total as f32
+        } else {
+            0.0
+        };
+
+        let summary = format!(
+            "Filtered: {count} processes using {} ",
+            human_bytes_fixed_unit(memory, self.memory_unit, false)
+        );
+        let [summary_area, meter_area] = Layout::horizontal([
+            Constraint::Length(summary.len() as u16),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+        Line::from(summary).render(summary_area, buf);
+        MeterBar::new(percent)
+            .title("RAM")
+            .precision(self.precision)
+            .style(
+                Style::default()
+                    .green()
+                    .patch(severity_style(percent, &self.theme)),
+            )
+            .render(meter_area, buf);
+    }
+
+    /// Flash the info bar when a threshold is first crossed, but not again until the
+    /// values have gone back below the thresholds
+    fn check_alerts(&mut self) {
+        let alerting = self.alerts.triggered(&self.current_data);
+        if alerting && !self.alerting {
+            self.flash_until = Some(Instant::now() + FLASH_DURATION);
+            if self.alerts.bell {
+                let mut stdout = io::stdout();
+                let _ = stdout.write_all(b"\x07").and_then(|_| stdout.flush());
+            }
+        }
+        self.alerting = alerting;
+    }
+
+    fn sort(&mut self) {
+        let sort = self.sort;
+        if sort.key == SortKey::Cpu && self.show_threads && self.collapse_threads {
+            // Sort processes by the summed CPU usage they are shown with
+            let totals =
+                thread_cpu_totals(&self.current_data.processes, ProcessInfo::sort_cpu_usage);
+            let cpu_usage = |p: &ProcessInfo| totals.get(&p.pid).copied().unwrap_or_default();
+            self.current_data.processes.sort_by(|a, b| {
+                sort.directed(cpu_usage(b).total_cmp(&cpu_usage(a)))
+                    .then_with(|| a.pid.cmp(&b.pid))
+            });
+        } else {
             self.current_data
                 .processes
-                .sort_by(|a, b| a.cpu_usage.partial_cmp(&b.cpu_usage).unwrap().reverse());
+                .sort_by(|a, b| sort.compare(a, b));
+        }
+    }
+
+    fn toggle_collapse_threads(&mut self) {
+        self.collapse_threads = !self.collapse_threads;
+        self.sort();
+    }
+
+    fn set_sort(&mut self, sort: SortSpec) {
+        self.sort = sort;
+        self.sort();
+    }
+
+    /// Start or stop choosing a core in the CPU panel to zoom in on
+    fn toggle_core_focus(&mut self) {
+        if self.core_focus.is_some() {
+            self.core_focus = None;
+            self.zoom_core = false;
+        } else {
+            self.core_focus = Some(0);
+            self.show_cores = true;
         }
     }
 
@@ -101,6 +952,68 @@ impl App {
         self.show_kernel_threads = !self.show_kernel_threads;
     }
 
+    fn toggle_privileged_only(&mut self) {
+        self.privileged_only = !self.privileged_only;
+
+        if let Some(tx) = &self.main_tx {
+            let _ = tx.send(Message::ReadCaps(self.needs_caps()));
+        }
+    }
+
+    /// Whether anything shown depends on the capabilities of the processes
+    fn needs_caps(&self) -> bool {
+        self.privileged_only || self.columns.contains(&Column::Privileged)
+    }
+
+    fn toggle_fd_column(&mut self) {
+        let show = self.toggle_columns(&[Column::Fd]);
+
+        if let Some(tx) = &self.main_tx {
+            let _ = tx.send(Message::ReadFds(show));
+        }
+    }
+
+    fn toggle_io_columns(&mut self) {
+        let show = self.toggle_columns(&Column::IO);
+
+        if let Some(tx) = &self.main_tx {
+            let _ = tx.send(Message::ReadIo(show));
+        }
+    }
+
+    fn toggle_gpu_memory_column(&mut self) {
+        let show = self.toggle_columns(&[Column::GpuMem]);
+
+        if let Some(tx) = &self.main_tx {
+            let _ = tx.send(Message::ReadGpuMemory(show));
+        }
+    }
+
+    fn toggle_swap_column(&mut self) {
+        let show = self.toggle_columns(&[Column::Swap]);
+
+        if let Some(tx) = &self.main_tx {
+            let _ = tx.send(Message::ReadSwap(show));
+        }
+    }
+
+    /// Remove `columns` if any of them are shown, otherwise add them before the command.
+    /// Returns whether the columns are shown now.
+    fn toggle_columns(&mut self, columns: &[Column]) -> bool {
+        if self.columns.iter().any(|c| columns.contains(c)) {
+            self.columns.retain(|c| !columns.contains(c));
+            false
+        } else {
+            let i = self
+                .columns
+                .iter()
+                .position(|c| *c == Column::Command)
+                .unwrap_or(self.columns.len());
+            self.columns.splice(i..i, columns.iter().copied());
+            true
+        }
+    }
+
     fn toggle_threads(&mut self) {
         self.show_threads = !self.show_threads;
 
@@ -111,26 +1024,357 @@ impl App {
 }
 
 impl Widget for &mut App {
-    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer)
+    fn render(self, area: Rect, buf: &mut Buffer)
     where
         Self: Sized,
     {
-        let mut cpu_info = CpuInfoWidget::new(&self.current_data, area.width - 47);
+        if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+            Paragraph::new(format!(
+                "Terminal too small\n{}x{}, need {MIN_WIDTH}x{MIN_HEIGHT}",
+                area.width, area.height
+            ))
+            .centered()
+            .wrap(Wrap { trim: true })
+            .render(area, buf);
+            return;
+        }
+
+        let mut cpu_info = CpuInfoWidget::new(&self.current_data, self.cpu_bars, &self.theme)
+            .show_cores(self.show_cores)
+            .busiest_first(self.cores_busiest_first)
+            .selected(self.core_focus)
+            .total_history(&self.cpu_history)
+            .precision(self.precision);
+        // The system info needs 47 columns, the rest can be used for the CPUs
+        let (cpu_width, cpu_height) = cpu_info.size(area.width.saturating_sub(47));
+
+        let gpus = self.current_data.gpus.as_deref().unwrap_or_default();
+        let mut gpu_info = GpuWidget::new(gpus, &self.theme)
+            .memory_unit(self.memory_unit)
+            .precision(self.precision);
 
         let vertical = Layout::vertical([
-            Constraint::Length(cpu_info.row_count().max(5) + 1),
+            Constraint::Length(cpu_height.max(5) + 1),
+            Constraint::Length(gpu_info.row_count()),
             Constraint::Fill(1),
         ]);
-        let [info_area, process_area] = vertical.areas(area);
+        let [info_area, gpu_area, process_area] = vertical.areas(area);
+        gpu_info.render(gpu_area, buf);
 
-        let info_horiz =
-            Layout::horizontal([Constraint::Fill(1), Constraint::Length(cpu_info.width())]);
+        let info_horiz = Layout::horizontal([Constraint::Fill(1), Constraint::Length(cpu_width)]);
         let [info_area, cpu_area] = info_horiz.areas(info_area);
 
-        SystemInfoWidget::new(&self.current_data).render(info_area, buf);
+        SystemInfoWidget::new(&self.current_data, &self.theme)
+            .peaks(self.show_peaks.then_some(&self.peaks))
+            .load_history(&self.load_history)
+            .memory_unit(self.memory_unit)
+            .precision(self.precision)
+            .render(info_area, buf);
+        if self.flash_until.is_some_and(|until| Instant::now() < until) {
+            buf.set_style(info_area, Style::new().reversed());
+        }
         cpu_info.render(cpu_area, buf);
-        ProcessList::new(&self.current_data)
-            .show_kernel_threads(self.show_kernel_threads)
-            .render(process_area, buf);
+
+        let command = self.selected_command();
+        let command_height = command.as_ref().map_or(0, |command| {
+            (command.line_count(area.width) as u16).min(MAX_COMMAND_LINES)
+        });
+        let show_totals = !self.filter.is_empty();
+        let [process_area, command_area, totals_area, status_area] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(command_height),
+            Constraint::Length(show_totals.into()),
+            Constraint::Length(1),
+        ])
+        .areas(process_area);
+        if let Some(command) = command {
+            command.render(command_area, buf);
+        }
+        if show_totals {
+            self.render_filter_totals(totals_area, buf);
+        }
+        match self.input_mode {
+            InputMode::Search => Line::from(format!("/{}", self.search)),
+            InputMode::Filter => Line::from(format!("Filter: {}", self.filter)),
+            InputMode::EnvFilter => Line::from(format!("Env: {}", self.env_filter)),
+            InputMode::Normal if self.quit_pending => self.quit_prompt(),
+            InputMode::Normal => self.status_line(),
+        }
+        .render(status_area, buf);
+
+        // The header row takes up one line of the process area
+        self.page_size = process_area.height.saturating_sub(1);
+        let zoomed_core = self
+            .core_focus
+            .filter(|_| self.zoom_core)
+            .and_then(|focus| self.current_data.cpu_usage.as_ref()?.cores.get(focus))
+            .map(|(cpu, _)| *cpu);
+        if let Some(cpu) = zoomed_core {
+            let empty = History::new(self.history_len);
+            let history = self.core_history.get(&cpu).unwrap_or(&empty);
+            CoreHistoryWidget::new(cpu, history, &self.theme)
+                .precision(self.precision)
+                .render(process_area, buf);
+        } else if let Some(group_by) = self.group_by {
+            GroupList::new(&self.current_data, group_by, &self.theme)
+                .show_kernel_threads(self.show_kernel_threads)
+                .sort(self.group_sort)
+                .memory_unit(self.memory_unit)
+                .precision(self.precision)
+                .render(process_area, buf, &mut self.group_table_state);
+        } else {
+            ProcessList::new(&self.current_data, &self.theme)
+                .show_kernel_threads(self.show_kernel_threads)
+                .privileged_only(self.privileged_only)
+                .collapse_threads(self.show_threads && self.collapse_threads)
+                .tree(self.tree)
+                .name_mode(self.name_mode)
+                .max_name_width(self.max_name_width)
+                .memory_unit(self.memory_unit)
+                .precision(self.precision)
+                .columns(&self.columns)
+                .search(&self.search)
+                .filter(&self.filter)
+                .invert_filter(self.invert_filter)
+                .env_matches((!self.env_filter.is_empty()).then_some(&self.env_matches))
+                .marked(&self.marked)
+                .state_flashes(&self.state_flashes)
+                .render(process_area, buf, &mut self.table_state);
+        }
+
+        if self.show_errors {
+            ErrorLogWidget::new(&self.errors, self.errors_scroll).render(area, buf);
+        } else if self.show_details && self.group_by.is_none() && zoomed_core.is_none() {
+            if let Some(process) = self.selected_process() {
+                let details = self
+                    .details
+                    .as_ref()
+                    .filter(|(pid, _)| *pid == process.pid)
+                    .map(|(_, details)| details);
+                ProcessDetailWidget::new(process, details).render(area, buf);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort_key::Direction;
+
+    fn system(pids: impl IntoIterator<Item = i32>) -> System {
+        System {
+            processes: pids
+                .into_iter()
+                .map(|pid| ProcessInfo {
+                    pid,
+                    tgid: pid,
+                    ..ProcessInfo::default()
+                })
+                .collect(),
+            ..System::default()
+        }
+    }
+
+    #[test]
+    fn small_terminal_shows_message() {
+        let mut app = App::new(false, true);
+        app.set_data(system(1..=20));
+
+        for width in 0..30 {
+            for height in 0..15 {
+                let area = Rect::new(0, 0, width, height);
+                app.render(area, &mut Buffer::empty(area));
+            }
+        }
+
+        let area = Rect::new(0, 0, 19, 10);
+        let mut buf = Buffer::empty(area);
+        app.render(area, &mut buf);
+        let first_line: String = (0..area.width).map(|x| buf[(x, 0)].symbol()).collect();
+        assert_eq!(first_line.trim(), "Terminal too small");
+    }
+
+    #[test]
+    fn set_data_keeps_scroll_offset() {
+        let mut app = App::new(false, true).sort_by(SortSpec::new(SortKey::Pid));
+        app.set_data(system(1..=20));
+        *app.table_state.offset_mut() = 5;
+
+        app.set_data(system(1..=20));
+        assert_eq!(app.table_state.offset(), 5);
+
+        app.set_data(system(1..=3));
+        assert_eq!(app.table_state.offset(), 2);
+    }
+
+    #[test]
+    fn set_data_follows_selected_process() {
+        let mut app = App::new(false, true).sort_by(SortSpec::new(SortKey::Pid));
+        app.set_data(system(1..=20));
+        app.table_state.select(Some(9));
+
+        app.set_data(system(6..=20));
+        assert_eq!(app.table_state.selected(), Some(4));
+        assert_eq!(app.selected_process().map(|p| p.pid), Some(10));
+    }
+
+    #[test]
+    fn selection_persists_across_renders() {
+        let mut app = App::new(false, true).sort_by(SortSpec::new(SortKey::Pid));
+        app.set_data(system(1..=20));
+        app.table_state.select(Some(3));
+
+        let area = Rect::new(0, 0, 80, 30);
+        for _ in 0..2 {
+            app.render(area, &mut Buffer::empty(area));
+            assert_eq!(app.table_state.selected(), Some(3));
+            assert_eq!(app.selected_process().map(|p| p.pid), Some(4));
+        }
+    }
+
+    #[test]
+    fn down_moves_selection_between_renders() {
+        let mut app = App::new(false, true).sort_by(SortSpec::new(SortKey::Pid));
+        app.set_data(system(1..=20));
+        let area = Rect::new(0, 0, 80, 30);
+        app.render(area, &mut Buffer::empty(area));
+
+        for _ in 0..2 {
+            app.handle_key_event(KeyEvent::from(KeyCode::Down));
+            app.render(area, &mut Buffer::empty(area));
+        }
+        assert_eq!(app.table_state.selected(), Some(2));
+        assert_eq!(app.selected_process().map(|p| p.pid), Some(3));
+    }
+
+    #[test]
+    fn quits_when_watched_process_exits() {
+        let mut app = App::new(false, true)
+            .sort_by(SortSpec::new(SortKey::Pid))
+            .watch_pid(Some(5), Some(3));
+        app.handle_msg(system(1..=10));
+        assert_eq!(app.selected_process().map(|p| p.pid), Some(5));
+        assert!(!app.exit);
+
+        app.handle_msg(system(6..=10));
+        assert!(app.exit);
+        assert_eq!(app.exit_code(), Some(3));
+    }
+
+    #[test]
+    fn state_change_flashes_for_a_few_samples() {
+        let mut app = App::new(false, true);
+        app.handle_msg(system(1..=3));
+
+        let mut stuck = system(1..=3);
+        stuck.processes[1].state = State::Waiting;
+        stuck.processes[2].state = State::Running;
+        app.handle_msg(stuck);
+        // Becoming runnable isn't worth pointing out
+        assert_eq!(app.state_flashes.keys().collect::<Vec<_>>(), [&2]);
+
+        let mut still_stuck = system(1..=3);
+        still_stuck.processes[1].state = State::Waiting;
+        app.handle_msg(still_stuck);
+        assert!(app.state_flashes.contains_key(&2));
+        app.handle_msg(system(1..=3));
+        assert!(app.state_flashes.is_empty());
+    }
+
+    #[test]
+    fn grouped_views_have_their_own_sort() {
+        let mut app = App::new(false, true).sort_by(SortSpec::new(SortKey::Pid));
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('u')));
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('>')));
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('I')));
+        assert_eq!(app.group_sort.key, GroupSortKey::Res);
+        assert_eq!(app.group_sort.direction, Direction::Ascending);
+        assert_eq!(app.sort, SortSpec::new(SortKey::Pid));
+
+        // Back in the process list the keys change the process sort again
+        for _ in 0..3 {
+            app.handle_key_event(KeyEvent::from(KeyCode::Char('u')));
+        }
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('>')));
+        assert_eq!(app.sort, SortSpec::new(SortKey::Name));
+        assert_eq!(app.group_sort.key, GroupSortKey::Res);
+    }
+
+    #[test]
+    fn selected_command_is_shown_in_full() {
+        let mut app = App::new(false, true).sort_by(SortSpec::new(SortKey::Pid));
+        let mut data = system(1..=20);
+        data.processes[0].args = ["tail", "-f"]
+            .into_iter()
+            .chain(["/var/log/a.log"; 6])
+            .map(str::to_string)
+            .collect();
+        data.processes[0].cmdline = data.processes[0].args.join(" ");
+        app.set_data(data);
+        let area = Rect::new(0, 0, 60, 30);
+        let mut buf = Buffer::empty(area);
+        app.render(area, &mut buf);
+
+        let line = |y: u16| {
+            (0..area.width)
+                .map(|x| buf[(x, y)].symbol())
+                .collect::<String>()
+        };
+        // Above the status line
+        let logs = ["/var/log/a.log"; 3].join(" ");
+        assert_eq!(line(27).trim_end(), format!("tail -f {logs}"));
+        assert_eq!(line(28).trim_end(), logs);
+        assert!(line(29).starts_with("Sort: "));
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('L')));
+        let mut buf = Buffer::empty(area);
+        app.render(area, &mut buf);
+        assert!(!(0..area.width)
+            .map(|x| buf[(x, 28)].symbol())
+            .collect::<String>()
+            .contains("/var/log"));
+    }
+
+    #[test]
+    fn invert_needs_a_filter() {
+        let mut app = App::new(false, true);
+        let key = |app: &mut App, code| app.handle_key_event(KeyEvent::from(code));
+
+        key(&mut app, KeyCode::Char('!'));
+        assert!(!app.invert_filter);
+
+        for code in [KeyCode::Char('\\'), KeyCode::Char('x'), KeyCode::Enter] {
+            key(&mut app, code);
+        }
+        key(&mut app, KeyCode::Char('!'));
+        assert!(app.invert_filter);
+
+        // Erasing the filter also drops the inversion
+        for code in [KeyCode::Char('\\'), KeyCode::Backspace, KeyCode::Enter] {
+            key(&mut app, code);
+        }
+        assert!(app.filter.is_empty());
+        assert!(!app.invert_filter);
+    }
+
+    #[test]
+    fn env_filter_reads_environments_from_the_source() {
+        let (tx, rx) = mpsc::channel();
+        let mut app = App::new(false, true);
+        app.main_tx = Some(tx);
+
+        app.set_env_filter("LANG=C".to_string());
+        app.request_reads();
+        assert!(matches!(rx.try_recv(), Ok(Message::ReadEnviron(true))));
+
+        let mut data = system(1..=3);
+        data.processes[1].environ = Some(vec!["LANG=C".to_string()]);
+        app.set_data(data);
+        assert_eq!(app.env_matches, HashSet::from([2]));
+
+        app.set_env_filter(String::new());
+        app.request_reads();
+        assert!(matches!(rx.try_recv(), Ok(Message::ReadEnviron(false))));
     }
 }
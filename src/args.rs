@@ -0,0 +1,137 @@
+use std::{path::PathBuf, time::Duration};
+
+use clap::Parser;
+
+use crate::{
+    column::Column, history::MAX_HISTORY_DURATION, proc, process_list::NameMode,
+    sort_key::SortSpec, status::StatusFormat, utils::MemoryUnit,
+};
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Args {
+    /// Write a debug log to the given file
+    #[arg(long, value_name = "PATH")]
+    pub log: Option<PathBuf>,
+
+    /// Show kernel threads on startup
+    #[arg(long)]
+    pub kernel_threads: bool,
+
+    /// Show processes instead of threads on startup
+    #[arg(long)]
+    pub no_threads: bool,
+
+    /// Only show the aggregate CPU usage on startup, not each core
+    #[arg(long)]
+    pub hide_cores: bool,
+
+    /// Show the processes as a tree on startup, with the siblings sorted by --sort
+    #[arg(long)]
+    pub tree: bool,
+
+    /// Leave the last frame on screen after quitting instead of clearing it
+    #[arg(long)]
+    pub keep_frame: bool,
+
+    /// Keep the process with this PID selected and show its usage in the status line
+    #[arg(long, value_name = "PID")]
+    pub watch_pid: Option<i32>,
+
+    /// Quit with this exit code once the process given with --watch-pid exits
+    #[arg(long, value_name = "CODE", requires = "watch_pid")]
+    pub watch_exit_code: Option<i32>,
+
+    /// Initial column to sort the process list by, optionally followed by :asc or :desc,
+    /// e.g. res:desc
+    #[arg(long, value_name = "KEY[:DIRECTION]", default_value = "cpu")]
+    pub sort: SortSpec,
+
+    /// Only show processes whose name or command contains this text
+    #[arg(long, value_name = "TEXT")]
+    pub filter: Option<String>,
+
+    /// Read processes from proc mounted at the given path, e.g. a container's proc
+    #[arg(long, value_name = "PATH", env = "FELL_PROC_ROOT", default_value = proc::DEFAULT_ROOT)]
+    pub proc_root: PathBuf,
+
+    /// Record every sample to the given file
+    #[arg(long, value_name = "PATH", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Play back samples recorded with --record instead of reading proc
+    #[arg(long, value_name = "PATH")]
+    pub replay: Option<PathBuf>,
+
+    /// Read the config from the given file instead of ~/.config/fell/config.toml
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Comma separated list of columns to show in the process list
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub columns: Option<Vec<Column>>,
+
+    /// Usage percentage above which values are shown as a warning
+    #[arg(long, value_name = "PERCENT")]
+    pub warning_threshold: Option<f32>,
+
+    /// Usage percentage above which values are shown as critical
+    #[arg(long, value_name = "PERCENT")]
+    pub critical_threshold: Option<f32>,
+
+    /// Smooth CPU percentages over the previous samples, with ALPHA between 0 and 1 the
+    /// weight of the newest sample. Lower is steadier, sorting still uses the newest sample
+    #[arg(long, value_name = "ALPHA", value_parser = parse_alpha)]
+    pub cpu_smoothing: Option<f32>,
+
+    /// How many seconds back the history graphs go, at most a day
+    #[arg(long, value_name = "SECONDS", value_parser = parse_history)]
+    pub history: Option<u64>,
+
+    /// Show memory in this unit instead of scaling each value on its own
+    #[arg(long, value_enum, value_name = "UNIT")]
+    pub memory_unit: Option<MemoryUnit>,
+
+    /// What the Name column shows at start, changed with 'a' while running
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub name_mode: Option<NameMode>,
+
+    /// Print a single status line and exit, e.g. for a tmux or polybar status bar.
+    /// FORMAT replaces status_format from the config, with fields such as {uptime},
+    /// {load}, {mem} and {cpu}
+    #[arg(long, value_name = "FORMAT", num_args = 0..=1)]
+    pub status: Option<Option<StatusFormat>>,
+
+    /// Print the N processes using the most CPU and the most memory and exit
+    #[arg(long, value_name = "N", conflicts_with = "status")]
+    pub top: Option<usize>,
+
+    /// Seconds between the two samples --top calculates CPU usage from
+    #[arg(short, long, value_name = "SECONDS", requires = "top", value_parser = parse_delay)]
+    pub delay: Option<Duration>,
+}
+
+fn parse_alpha(input: &str) -> Result<f32, String> {
+    match input.parse::<f32>() {
+        Ok(alpha) if alpha > 0.0 && alpha <= 1.0 => Ok(alpha),
+        _ => Err("must be a number greater than 0 and at most 1".to_string()),
+    }
+}
+
+fn parse_history(input: &str) -> Result<u64, String> {
+    match input.parse::<u64>() {
+        Ok(seconds) if seconds <= MAX_HISTORY_DURATION.as_secs() => Ok(seconds),
+        _ => Err(format!(
+            "must be a number of seconds up to {}",
+            MAX_HISTORY_DURATION.as_secs()
+        )),
+    }
+}
+
+fn parse_delay(input: &str) -> Result<Duration, String> {
+    input
+        .parse::<f64>()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        .ok_or_else(|| "must be a number of seconds".to_string())
+}
@@ -0,0 +1,158 @@
+use clap::ValueEnum;
+use ratatui::layout::Constraint;
+use serde::Deserialize;
+
+use crate::{
+    proc::process_info::ProcessInfo,
+    process_list::DEFAULT_MAX_NAME_WIDTH,
+    utils::{human_bytes_fixed_unit, MemoryUnit, Precision, UserCache},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Column {
+    Pid,
+    User,
+    Name,
+    Virt,
+    Res,
+    State,
+    Cpu,
+    Fd,
+    Syscr,
+    Syscw,
+    Majflt,
+    Privileged,
+    Sched,
+    GpuMem,
+    Swap,
+    Command,
+}
+
+impl Column {
+    pub const DEFAULT: [Column; 8] = [
+        Column::Pid,
+        Column::User,
+        Column::Name,
+        Column::Virt,
+        Column::Res,
+        Column::State,
+        Column::Cpu,
+        Column::Command,
+    ];
+
+    /// The columns read from `/proc/[pid]/io`, which is only read while one is shown
+    pub const IO: [Column; 2] = [Column::Syscr, Column::Syscw];
+
+    pub fn header(&self) -> &'static str {
+        match self {
+            Column::Pid => "PID",
+            Column::User => "User",
+            Column::Name => "Name",
+            Column::Virt => "Virt",
+            Column::Res => "Res",
+            Column::State => "S",
+            Column::Cpu => "CPU%",
+            Column::Fd => "FD",
+            Column::Syscr => "SYSCR/s",
+            Column::Syscw => "SYSCW/s",
+            Column::Majflt => "MAJFLT/s",
+            Column::Privileged => "Priv",
+            Column::Sched => "Sched",
+            Column::GpuMem => "GPU MEM",
+            Column::Swap => "Swp",
+            Column::Command => "Command",
+        }
+    }
+
+    pub fn width(&self, precision: Precision) -> Constraint {
+        match self {
+            Column::Pid => Constraint::Max(7),
+            Column::User => Constraint::Max(10),
+            Column::Name => Constraint::Max(DEFAULT_MAX_NAME_WIDTH),
+            Column::Virt => Constraint::Length(8),
+            Column::Res => Constraint::Length(8),
+            Column::State => Constraint::Length(1),
+            Column::Cpu => Constraint::Length(precision.width()),
+            Column::Fd => Constraint::Length(5),
+            Column::Syscr | Column::Syscw => Constraint::Length(7),
+            Column::Majflt => Constraint::Length(8),
+            Column::Privileged => Constraint::Length(4),
+            Column::Sched => Constraint::Length(7),
+            Column::GpuMem => Constraint::Length(8),
+            Column::Swap => Constraint::Length(3),
+            Column::Command => Constraint::Fill(1),
+        }
+    }
+
+    pub fn value(
+        &self,
+        process: &ProcessInfo,
+        users: &mut UserCache,
+        memory_unit: MemoryUnit,
+        precision: Precision,
+    ) -> String {
+        match self {
+            Column::Pid => format!("{:>7}", process.pid),
+            Column::User => process
+                .uid
+                .map(|uid| users.get(uid).to_string())
+                .unwrap_or_default(),
+            Column::Name => process.name.clone(),
+            Column::Virt => human_bytes_fixed_unit(process.virtual_memory, memory_unit, true),
+            Column::Res => human_bytes_fixed_unit(process.memory, memory_unit, true),
+            Column::State => process.state.to_string(),
+            Column::Cpu => cpu_value(process.cpu_usage, precision),
+            Column::Fd => process
+                .fd_count
+                .map(|count| format!("{count:>5}"))
+                .unwrap_or_default(),
+            Column::Syscr => syscall_value(process.syscall_rate.map(|rate| rate.reads)),
+            Column::Syscw => syscall_value(process.syscall_rate.map(|rate| rate.writes)),
+            Column::Majflt => process
+                .major_fault_rate
+                .map(|rate| format!("{rate:>8.1}"))
+                .unwrap_or_default(),
+            Column::Privileged => if process.uid == Some(0) {
+                "root"
+            } else if process.is_privileged() {
+                "caps"
+            } else {
+                ""
+            }
+            .to_string(),
+            Column::Sched => match process.sched_policy {
+                Some(policy) if policy.is_realtime() => {
+                    format!("{policy} {}", process.rt_priority)
+                }
+                Some(policy) => policy.to_string(),
+                None => String::default(),
+            },
+            Column::GpuMem => process
+                .gpu_memory
+                .map(|memory| human_bytes_fixed_unit(memory as usize, memory_unit, true))
+                .unwrap_or_default(),
+            // Only whether anything is swapped out, to spot the processes that are
+            Column::Swap => match process.swap {
+                Some(swap) if swap > 0 => "yes".to_string(),
+                _ => String::default(),
+            },
+            // Kernel threads and zombies have no command line, show their name like ps does
+            Column::Command if process.cmdline.is_empty() => format!("[{}]", process.name),
+            Column::Command => process.cmdline.clone(),
+        }
+    }
+}
+
+/// Format a CPU usage the way the CPU% column shows it. A dash marks processes that
+/// haven't been measured yet, which isn't the same as using no CPU.
+pub fn cpu_value(cpu_usage: Option<f32>, precision: Precision) -> String {
+    match cpu_usage {
+        Some(cpu_usage) => precision.percent_fixed(cpu_usage),
+        None => format!("{:>1$}", "—", precision.width() as usize),
+    }
+}
+
+fn syscall_value(rate: Option<f32>) -> String {
+    rate.map(|rate| format!("{rate:>7.0}")).unwrap_or_default()
+}
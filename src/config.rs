@@ -0,0 +1,151 @@
+use std::{env, fs, path::Path, path::PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    alert::Alerts,
+    args::Args,
+    column::Column,
+    history::{DEFAULT_HISTORY_DURATION, MAX_HISTORY_DURATION},
+    process_list::{NameMode, DEFAULT_MAX_NAME_WIDTH},
+    status::StatusFormat,
+    theme::Theme,
+    utils::{MemoryUnit, Precision},
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub columns: Vec<Column>,
+    pub theme: Theme,
+    pub alerts: Alerts,
+    /// What the Name column shows at start, until it is changed with 'a' while running,
+    /// which is then remembered for the next run
+    pub name_mode: NameMode,
+    /// Widest the Name column grows to fit the names shown
+    pub max_name_width: u16,
+    /// Ask before quitting while processes are marked or the details are open
+    pub confirm_quit: bool,
+    /// Leave the last frame on screen after quitting instead of clearing it
+    pub keep_frame: bool,
+    /// Only show the aggregate CPU usage, not each core
+    pub hide_cores: bool,
+    /// Show the processes as a tree
+    pub tree: bool,
+    /// Flash the rows of processes that just became stuck, zombies or stopped
+    pub flash_state_changes: bool,
+    /// Weight of the newest sample when smoothing CPU percentages, between 0 and 1, or
+    /// `None` to show each sample as is
+    pub cpu_smoothing: Option<f32>,
+    /// Unit to show memory in, instead of scaling each value on its own
+    pub memory_unit: MemoryUnit,
+    /// Number of decimals percentages are shown with, from 0 to 2
+    pub percent_decimals: Precision,
+    /// How many seconds back the history graphs go, at most a day
+    #[serde(deserialize_with = "deserialize_history_seconds")]
+    pub history_seconds: u64,
+    /// Line printed by `--status`
+    pub status_format: StatusFormat,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            columns: Column::DEFAULT.to_vec(),
+            theme: Theme::default(),
+            alerts: Alerts::default(),
+            name_mode: NameMode::Comm,
+            max_name_width: DEFAULT_MAX_NAME_WIDTH,
+            confirm_quit: true,
+            keep_frame: false,
+            hide_cores: false,
+            tree: false,
+            flash_state_changes: true,
+            cpu_smoothing: None,
+            memory_unit: MemoryUnit::Auto,
+            percent_decimals: Precision::default(),
+            history_seconds: DEFAULT_HISTORY_DURATION.as_secs(),
+            status_format: StatusFormat::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Load the config from `path`, or from the default location if no path is given.
+    /// A missing config file at the default location is not an error.
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Config::default()),
+            },
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(input) => toml::from_str(&input)
+                .with_context(|| format!("Failed to parse config file {}", path.display())),
+            Err(_) if !required => Ok(Config::default()),
+            Err(err) => {
+                Err(err).with_context(|| format!("Failed to read config file {}", path.display()))
+            }
+        }
+    }
+
+    /// Override config values with the ones given on the command line
+    pub fn apply_args(&mut self, args: &Args) {
+        if let Some(columns) = &args.columns {
+            self.columns = columns.clone();
+        }
+        if let Some(threshold) = args.warning_threshold {
+            self.theme.warning_threshold = threshold;
+        }
+        if let Some(threshold) = args.critical_threshold {
+            self.theme.critical_threshold = threshold;
+        }
+        if args.hide_cores {
+            self.hide_cores = true;
+        }
+        if args.keep_frame {
+            self.keep_frame = true;
+        }
+        if args.tree {
+            self.tree = true;
+        }
+        if let Some(alpha) = args.cpu_smoothing {
+            self.cpu_smoothing = Some(alpha);
+        }
+        if let Some(seconds) = args.history {
+            self.history_seconds = seconds;
+        }
+        if let Some(unit) = args.memory_unit {
+            self.memory_unit = unit;
+        }
+        if let Some(mode) = args.name_mode {
+            self.name_mode = mode;
+        }
+    }
+}
+
+fn deserialize_history_seconds<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<u64, D::Error> {
+    let seconds = u64::deserialize(deserializer)?;
+    if seconds > MAX_HISTORY_DURATION.as_secs() {
+        return Err(serde::de::Error::custom(format!(
+            "history_seconds must be at most {}",
+            MAX_HISTORY_DURATION.as_secs()
+        )));
+    }
+
+    Ok(seconds)
+}
+
+fn default_path() -> Option<PathBuf> {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_dir.join("fell").join("config.toml"))
+}
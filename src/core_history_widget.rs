@@ -0,0 +1,85 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Style, Stylize},
+    symbols::Marker,
+    text::Line,
+    widgets::{Axis, Block, Chart, Dataset, GraphType, Widget},
+};
+
+use crate::{
+    history::History,
+    sysinfo_thread::INTERVAL,
+    theme::{severity_style, Theme},
+    utils::Precision,
+};
+
+/// Graph of the usage of a single core over the time covered by its history
+pub struct CoreHistoryWidget<'a> {
+    cpu: u32,
+    history: &'a History<f32>,
+    precision: Precision,
+    theme: &'a Theme,
+}
+
+impl<'a> CoreHistoryWidget<'a> {
+    pub fn new(cpu: u32, history: &'a History<f32>, theme: &'a Theme) -> Self {
+        CoreHistoryWidget {
+            cpu,
+            history,
+            precision: Precision::default(),
+            theme,
+        }
+    }
+
+    pub fn precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+
+        self
+    }
+}
+
+impl Widget for &CoreHistoryWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        // The newest sample is at the right edge, so a short history grows from there
+        let len = self.history.capacity();
+        let start = len.saturating_sub(self.history.len());
+        let points: Vec<(f64, f64)> = self
+            .history
+            .iter()
+            .enumerate()
+            .map(|(i, usage)| ((start + i) as f64, *usage as f64))
+            .collect();
+        let current = self.history.latest().copied().unwrap_or_default();
+        let span = INTERVAL * len as u32;
+
+        let dataset = Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(
+                Style::default()
+                    .green()
+                    .patch(severity_style(current, self.theme)),
+            )
+            .data(&points);
+        Chart::new(vec![dataset])
+            .block(Block::bordered().title(Line::from(vec![
+                format!(" CPU {} ", self.cpu).into(),
+                format!("{} ", self.precision.percent(current)).bold(),
+            ])))
+            .x_axis(
+                Axis::default()
+                    .bounds([0.0, len.saturating_sub(1) as f64])
+                    .labels([format!("-{}s", span.as_secs()), "now".to_string()]),
+            )
+            .y_axis(
+                Axis::default()
+                    .bounds([0.0, 100.0])
+                    .labels(["0%", "50%", "100%"]),
+            )
+            .render(area, buf);
+    }
+}
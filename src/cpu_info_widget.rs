@@ -1,73 +1,375 @@
 use ratatui::{
     buffer::Buffer,
-    layout::Rect,
+    layout::{Constraint, Layout, Rect},
     style::{Style, Styled, Stylize},
     text::Line,
-    widgets::{Paragraph, Widget},
+    widgets::{Paragraph, Sparkline, Widget},
 };
 
-use crate::proc::System;
+use crate::{
+    history::History,
+    meter_bar::MeterBar,
+    proc::{EventRate, System},
+    theme::{severity_style, Theme},
+    utils::Precision,
+};
 
-const COL_SIZE: u16 = 11;
+/// Width of `"{:3}: "` in front of the usage of each core
+const CORE_LABEL_WIDTH: u16 = 5;
+const BAR_COL_SIZE: u16 = 20;
+const MIN_METER_WIDTH: u16 = 30;
 
 pub struct CpuInfoWidget<'a> {
-    cpu_lines: Vec<Line<'a>>,
-    width: u16,
+    total: Option<f32>,
+    cores: Vec<(u32, f32)>,
+    /// Number of cores above the warning threshold
+    busy: usize,
+    event_rate: Option<EventRate>,
+    bars: bool,
+    show_cores: bool,
+    busiest_first: bool,
+    selected: Option<usize>,
+    total_history: Option<&'a History<f32>>,
+    precision: Precision,
+    theme: &'a Theme,
 }
 
 impl<'a> CpuInfoWidget<'a> {
-    pub fn new(data: &'a System, width: u16) -> Self {
-        let cols = width / COL_SIZE;
-        let cpu_lines: Vec<Line> = if let Some(cpu_percents) = &data.cpu_usage {
-            cpu_percents[1..cpu_percents.len()]
+    pub fn new(data: &System, bars: bool, theme: &'a Theme) -> Self {
+        let (total, cores) = if let Some(cpu_usage) = &data.cpu_usage {
+            (Some(cpu_usage.total), cpu_usage.cores.clone())
+        } else {
+            (None, Vec::default())
+        };
+
+        let busy = cores
+            .iter()
+            .filter(|(_, percent)| *percent > theme.warning_threshold)
+            .count();
+
+        CpuInfoWidget {
+            total,
+            cores,
+            busy,
+            event_rate: data.event_rate,
+            bars,
+            show_cores: true,
+            busiest_first: false,
+            selected: None,
+            total_history: None,
+            precision: Precision::default(),
+            theme,
+        }
+    }
+
+    /// Show the usage of each core under the aggregate, on by default
+    pub fn show_cores(mut self, show: bool) -> Self {
+        self.show_cores = show;
+
+        self
+    }
+
+    /// Order the cores by their usage, busiest first, instead of by their number
+    pub fn busiest_first(mut self, busiest_first: bool) -> Self {
+        self.busiest_first = busiest_first;
+
+        self
+    }
+
+    /// Highlight the core at index `selected`
+    pub fn selected(mut self, selected: Option<usize>) -> Self {
+        self.selected = selected;
+
+        self
+    }
+
+    /// Graph the aggregate usage under its meter, whether or not the cores are shown
+    pub fn total_history(mut self, history: &'a History<f32>) -> Self {
+        self.total_history = Some(history);
+
+        self
+    }
+
+    pub fn precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+
+        self
+    }
+
+    fn col_size(&self) -> u16 {
+        if self.bars {
+            BAR_COL_SIZE
+        } else {
+            // The usage and a space between the cores
+            CORE_LABEL_WIDTH + self.precision.width() + 1
+        }
+    }
+
+    /// Number of cores shown side by side in `width` columns
+    fn cols(&self, width: u16) -> u16 {
+        (width / self.col_size()).max(1)
+    }
+
+    fn core_rows(&self, cols: u16) -> u16 {
+        if self.show_cores {
+            (self.cores.len() as u16).div_ceil(cols)
+        } else {
+            0
+        }
+    }
+
+    /// Width and height needed to show every core when given at most `max_width` columns.
+    /// Rendering into an area of exactly this size lays the cores out the same way.
+    pub fn size(&self, max_width: u16) -> (u16, u16) {
+        if self.total.is_none() {
+            return (("Calculating...".len() as u16).min(max_width), 1);
+        }
+
+        let cols = self.cols(max_width);
+        let rows = self.core_rows(cols);
+        let width = match rows {
+            0 => MIN_METER_WIDTH,
+            1 => (self.cores.len() as u16 * self.col_size()).max(MIN_METER_WIDTH),
+            _ => cols * self.col_size(),
+        };
+
+        // The aggregate meter and its graph and the event rates surround the cores
+        (
+            width.min(max_width),
+            rows + 2 + u16::from(self.total_history.is_some()),
+        )
+    }
+
+    /// The cores in the order they are shown
+    fn shown_cores(&self) -> Vec<(u32, f32)> {
+        let mut cores = self.cores.clone();
+        if self.busiest_first {
+            // Stable, so equally busy cores stay in index order
+            cores.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        }
+
+        cores
+    }
+
+    fn core_style(&self, percent: f32) -> Style {
+        severity_style(percent, self.theme).bold()
+    }
+}
+
+impl<'a> Widget for &mut CpuInfoWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let Some(total) = self.total else {
+            Line::default().spans(["Calculating..."]).render(area, buf);
+            return;
+        };
+
+        let cols = self.cols(area.width);
+        let [total_area, history_area, area, event_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(self.total_history.is_some().into()),
+            Constraint::Length(self.core_rows(cols)),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+        MeterBar::new(total)
+            .title(format!("CPU {}/{} busy", self.busy, self.cores.len()))
+            .precision(self.precision)
+            .style(
+                Style::default()
+                    .green()
+                    .patch(severity_style(total, self.theme)),
+            )
+            .render(total_area, buf);
+        if let Some(history) = self.total_history {
+            let width = history_area.width.min(history.len() as u16);
+            let data: Vec<u64> = history
                 .iter()
-                .enumerate()
-                .collect::<Vec<(usize, &f32)>>()
+                .skip(history.len() - width as usize)
+                .map(|usage| usage.round().clamp(0.0, 100.0) as u64)
+                .collect();
+            Sparkline::default()
+                .data(&data)
+                .max(100)
+                .style(
+                    Style::default()
+                        .green()
+                        .patch(severity_style(total, self.theme)),
+                )
+                .render(history_area, buf);
+        }
+
+        let col_size = self.col_size();
+        let cell = |n: usize| {
+            Rect {
+                x: area.x + (n as u16 % cols) * col_size,
+                y: area.y + n as u16 / cols,
+                width: col_size - 1,
+                height: 1,
+            }
+            .intersection(area)
+        };
+        let cores = self.shown_cores();
+        if self.bars {
+            for (n, (i, p)) in cores.iter().enumerate() {
+                MeterBar::new(*p)
+                    .title(format!("{:3}", i))
+                    .precision(self.precision)
+                    .style(
+                        Style::default()
+                            .green()
+                            .patch(severity_style(*p, self.theme)),
+                    )
+                    .render(cell(n), buf);
+            }
+        } else {
+            let cpu_lines: Vec<Line> = cores
                 .chunks(cols as usize)
                 .map(|v| {
                     let mut line_spans = Vec::new();
                     for (i, p) in v {
-                        let number_style = if **p > 75.0 {
-                            Style::default().red().bold()
-                        } else if **p > 50.0 {
-                            Style::default().yellow().bold()
-                        } else {
-                            Style::default().bold()
-                        };
                         line_spans.push(format!("{:3}: ", i).into());
-                        line_spans.push(format!("{:5.1}% ", p).set_style(number_style));
+                        line_spans.push(
+                            format!("{} ", self.precision.percent_fixed(*p))
+                                .set_style(self.core_style(*p)),
+                        );
                     }
 
                     Line::default().spans(line_spans)
                 })
-                .collect()
-        } else {
-            vec![Line::default().spans(["Calculating..."])]
+                .collect();
+
+            Paragraph::new(cpu_lines).render(area, buf);
+        }
+        // The selection is an index into the cores in index order
+        let selected = self
+            .selected
+            .and_then(|selected| self.cores.get(selected))
+            .and_then(|(cpu, _)| cores.iter().position(|(i, _)| i == cpu));
+        if let Some(selected) = selected.filter(|_| self.show_cores) {
+            buf.set_style(cell(selected), Style::new().reversed());
+        }
+
+        let rate = |rate: Option<f32>| {
+            rate.map(|rate| format!("{rate:.0}"))
+                .unwrap_or_else(|| "-".to_string())
+                .set_style(Style::default().bold())
         };
+        Line::default()
+            .spans([
+                "Ctxt/s: ".into(),
+                rate(self.event_rate.map(|r| r.context_switches)),
+                " Intr/s: ".into(),
+                rate(self.event_rate.map(|r| r.interrupts)),
+            ])
+            .render(event_area, buf);
+    }
+}
 
-        let width = if cpu_lines.len() == 1 {
-            cpu_lines.first().unwrap().width() as u16
-        } else {
-            cols * COL_SIZE
+#[cfg(test)]
+mod tests {
+    use crate::proc::CpuUsage;
+
+    use super::*;
+
+    fn render(width: u16, show_cores: bool) -> (u16, Vec<String>) {
+        render_with(width, |cpu_info| cpu_info.show_cores(show_cores))
+    }
+
+    fn render_with(
+        width: u16,
+        configure: impl FnOnce(CpuInfoWidget) -> CpuInfoWidget,
+    ) -> (u16, Vec<String>) {
+        render_themed(width, &Theme::default(), configure)
+    }
+
+    fn render_themed<'a>(
+        width: u16,
+        theme: &'a Theme,
+        configure: impl FnOnce(CpuInfoWidget<'a>) -> CpuInfoWidget<'a>,
+    ) -> (u16, Vec<String>) {
+        let data = System {
+            cpu_usage: Some(CpuUsage {
+                total: 25.0,
+                cores: vec![(0, 10.0), (1, 20.0), (2, 30.0), (3, 40.0)],
+            }),
+            ..System::default()
         };
+        let mut cpu_info = configure(CpuInfoWidget::new(&data, false, theme));
+        let (width, height) = cpu_info.size(width);
+
+        let area = Rect::new(0, 0, width, height);
+        let mut buf = Buffer::empty(area);
+        cpu_info.render(area, &mut buf);
 
-        CpuInfoWidget { cpu_lines, width }
+        let lines = (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buf[(x, y)].symbol())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect();
+
+        (width, lines)
     }
 
-    pub fn row_count(&self) -> u16 {
-        self.cpu_lines.len() as u16
+    #[test]
+    fn cores_fill_the_given_width() {
+        let (width, lines) = render(50, true);
+        assert_eq!(width, 48);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], "  0:  10.0%   1:  20.0%   2:  30.0%   3:  40.0%");
+
+        let (width, lines) = render(30, true);
+        assert_eq!(width, 24);
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1], "  0:  10.0%   1:  20.0%");
+        assert_eq!(lines[2], "  2:  30.0%   3:  40.0%");
+        assert!(lines[3].starts_with("Ctxt/s: -"));
+
+        let (width, lines) = render(50, false);
+        assert_eq!(width, MIN_METER_WIDTH);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].starts_with("Ctxt/s: -"));
     }
 
-    pub fn width(&self) -> u16 {
-        self.width
+    #[test]
+    fn aggregate_history_is_graphed_below_the_meter() {
+        let mut history = History::new(10);
+        for usage in [0.0, 50.0, 100.0] {
+            history.push(usage);
+        }
+
+        let theme = Theme::default();
+        for show_cores in [true, false] {
+            let (_, lines) = render_themed(50, &theme, |cpu_info| {
+                cpu_info.show_cores(show_cores).total_history(&history)
+            });
+            assert_eq!(lines[1], " ▄█");
+            assert!(lines.last().unwrap().starts_with("Ctxt/s: -"));
+        }
     }
-}
 
-impl<'a> Widget for &mut CpuInfoWidget<'a> {
-    fn render(self, area: Rect, buf: &mut Buffer)
-    where
-        Self: Sized,
-    {
-        Paragraph::new(self.cpu_lines.clone()).render(area, buf);
+    #[test]
+    fn busiest_cores_first() {
+        let (_, lines) = render_with(50, |cpu_info| cpu_info.busiest_first(true));
+        assert_eq!(lines[1], "  3:  40.0%   2:  30.0%   1:  20.0%   0:  10.0%");
+    }
+
+    #[test]
+    fn cores_above_the_warning_threshold_are_busy() {
+        let (_, lines) = render_with(50, |cpu_info| cpu_info);
+        assert!(lines[0].starts_with("CPU 0/4 busy ["));
+
+        let theme = Theme {
+            warning_threshold: 25.0,
+            ..Theme::default()
+        };
+        let (_, lines) = render_themed(50, &theme, |cpu_info| cpu_info);
+        assert!(lines[0].starts_with("CPU 2/4 busy ["));
     }
 }
@@ -0,0 +1,144 @@
+use std::{collections::VecDeque, fmt::Display, time::SystemTime};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Clear, Paragraph, Widget, Wrap},
+};
+
+use crate::utils::human_datetime;
+
+/// Most distinct errors kept, the oldest are dropped first
+const ERROR_LOG_LEN: usize = 100;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    message: String,
+    /// When the error was last seen
+    time: SystemTime,
+    /// How many times the error has been seen
+    count: usize,
+}
+
+/// The most recent errors, newest first. An error that keeps happening, e.g. a process
+/// that fails to parse in every sample, is kept as one entry with a count.
+#[derive(Debug, Default)]
+pub struct ErrorLog {
+    entries: VecDeque<Entry>,
+}
+
+impl ErrorLog {
+    pub fn push(&mut self, err: &impl Display) {
+        self.push_at(err.to_string(), SystemTime::now());
+    }
+
+    fn push_at(&mut self, message: String, time: SystemTime) {
+        let count = match self
+            .entries
+            .iter()
+            .position(|entry| entry.message == message)
+        {
+            Some(i) => self.entries.remove(i).map_or(0, |entry| entry.count),
+            None => 0,
+        };
+        self.entries.push_front(Entry {
+            message,
+            time,
+            count: count + 1,
+        });
+        self.entries.truncate(ERROR_LOG_LEN);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Overlay listing the errors in an [`ErrorLog`], scrolled down by `scroll` lines
+pub struct ErrorLogWidget<'a> {
+    log: &'a ErrorLog,
+    scroll: u16,
+}
+
+impl<'a> ErrorLogWidget<'a> {
+    pub fn new(log: &'a ErrorLog, scroll: u16) -> Self {
+        ErrorLogWidget { log, scroll }
+    }
+}
+
+impl Widget for &ErrorLogWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let lines: Vec<Line> = if self.log.is_empty() {
+            vec!["No errors".into()]
+        } else {
+            self.log
+                .entries
+                .iter()
+                .map(|entry| {
+                    let mut spans = vec![human_datetime(entry.time).bold(), " ".into()];
+                    if entry.count > 1 {
+                        spans.push(format!("({}x) ", entry.count).red());
+                    }
+                    spans.push(entry.message.as_str().into());
+                    Line::default().spans(spans)
+                })
+                .collect()
+        };
+
+        let [area] = Layout::horizontal([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        Clear.render(area, buf);
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0))
+            .block(Block::bordered().title(" Errors "))
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn recurring_errors_are_counted() {
+        let mut log = ErrorLog::default();
+        let time = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        log.push_at("meminfo".to_string(), time(1));
+        log.push_at("process".to_string(), time(1));
+        log.push_at("meminfo".to_string(), time(2));
+
+        let entries: Vec<_> = log
+            .entries
+            .iter()
+            .map(|entry| (entry.message.as_str(), entry.time, entry.count))
+            .collect();
+        assert_eq!(entries, [("meminfo", time(2), 2), ("process", time(1), 1)]);
+
+        for i in 0..ERROR_LOG_LEN {
+            log.push_at(i.to_string(), time(3));
+        }
+        assert_eq!(log.len(), ERROR_LOG_LEN);
+        assert_eq!(log.entries[0].message, (ERROR_LOG_LEN - 1).to_string());
+    }
+}
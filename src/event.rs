@@ -1,21 +1,72 @@
-use std::{io, sync::mpsc, thread};
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
 
-use crossterm::event;
+use crossterm::{
+    event::{self, Event},
+    terminal,
+};
+use signal_hook::consts::SIGWINCH;
 
 use crate::Message;
 
-pub fn start_thread(tx: mpsc::Sender<Message>) -> io::Result<()> {
+/// How often the shutdown flag is checked while waiting for input. `poll` returns as soon
+/// as an event arrives, so this only bounds how long quitting takes.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+pub fn start_thread(
+    tx: mpsc::Sender<Message>,
+    shutdown: Arc<AtomicBool>,
+) -> io::Result<JoinHandle<()>> {
     thread::Builder::new()
         .name("fell-event".to_string())
-        .spawn(move || thread_main(tx))?;
-
-    Ok(())
+        .spawn(move || thread_main(tx, shutdown))
 }
 
-fn thread_main(tx: mpsc::Sender<Message>) {
-    while let Ok(event) = event::read() {
+fn thread_main(tx: mpsc::Sender<Message>, shutdown: Arc<AtomicBool>) {
+    // Some terminal multiplexers resize without crossterm noticing, so the window size
+    // signal is watched as well. A resize crossterm does report clears the flag, so that
+    // it isn't redrawn twice.
+    let resized = Arc::new(AtomicBool::new(false));
+    if let Err(err) = signal_hook::flag::register(SIGWINCH, resized.clone()) {
+        log::warn!("Failed to watch for SIGWINCH: {err}");
+    }
+
+    while !shutdown.load(Ordering::Relaxed) {
+        let event = match event::poll(POLL_INTERVAL) {
+            Ok(true) => match event::read() {
+                Ok(event) => event,
+                Err(_) => break,
+            },
+            Ok(false) => match resized_event(&resized) {
+                Some(event) => event,
+                None => continue,
+            },
+            Err(_) => break,
+        };
+
+        if matches!(event, Event::Resize(..)) {
+            resized.store(false, Ordering::Relaxed);
+        }
         if tx.send(Message::Event(event)).is_err() {
             break;
         }
     }
 }
+
+/// A resize event for the current size if a SIGWINCH arrived that crossterm didn't report
+fn resized_event(resized: &AtomicBool) -> Option<Event> {
+    if !resized.swap(false, Ordering::Relaxed) {
+        return None;
+    }
+
+    terminal::size()
+        .ok()
+        .map(|(columns, rows)| Event::Resize(columns, rows))
+}
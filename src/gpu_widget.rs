@@ -0,0 +1,84 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Styled, Stylize},
+    text::Line,
+    widgets::Widget,
+};
+
+use crate::{
+    meter_bar::MeterBar,
+    proc::GpuInfo,
+    theme::{severity_style, Theme},
+    utils::{human_bytes_fixed_unit, MemoryUnit, Precision},
+};
+
+const METER_WIDTH: u16 = 30;
+
+/// One row per GPU with its utilization and video memory
+pub struct GpuWidget<'a> {
+    gpus: &'a [GpuInfo],
+    memory_unit: MemoryUnit,
+    precision: Precision,
+    theme: &'a Theme,
+}
+
+impl<'a> GpuWidget<'a> {
+    pub fn new(gpus: &'a [GpuInfo], theme: &'a Theme) -> Self {
+        GpuWidget {
+            gpus,
+            memory_unit: MemoryUnit::Auto,
+            precision: Precision::default(),
+            theme,
+        }
+    }
+
+    pub fn memory_unit(mut self, unit: MemoryUnit) -> Self {
+        self.memory_unit = unit;
+
+        self
+    }
+
+    pub fn precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+
+        self
+    }
+
+    pub fn row_count(&self) -> u16 {
+        self.gpus.len() as u16
+    }
+}
+
+impl<'a> Widget for &mut GpuWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let bytes = |bytes: u64| human_bytes_fixed_unit(bytes as usize, self.memory_unit, false);
+
+        for (i, (gpu, row)) in self.gpus.iter().zip(area.rows()).enumerate() {
+            let [meter_area, memory_area] =
+                Layout::horizontal([Constraint::Length(METER_WIDTH), Constraint::Fill(1)])
+                    .areas(row);
+
+            MeterBar::new(gpu.utilization)
+                .title(format!("GPU{i}"))
+                .precision(self.precision)
+                .style(
+                    Style::default()
+                        .green()
+                        .patch(severity_style(gpu.utilization, self.theme)),
+                )
+                .render(meter_area, buf);
+            Line::default()
+                .spans([
+                    " VRAM: ".into(),
+                    format!("{}/{}", bytes(gpu.memory_used), bytes(gpu.memory_total))
+                        .set_style(Style::default().bold()),
+                    format!(" {}", gpu.name).into(),
+                ])
+                .render(memory_area, buf);
+        }
+    }
+}
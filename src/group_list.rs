@@ -0,0 +1,376 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Cell, Row, StatefulWidget, Table, TableState},
+};
+
+use crate::{
+    column::cpu_value,
+    proc::{
+        process_info::{ProcessInfo, ProcessType},
+        System,
+    },
+    sort_key::{Direction, Key, SortSpec},
+    theme::{severity_style, Theme},
+    utils::{human_bytes_fixed_unit, memory_width, MemoryUnit, Precision, UserCache},
+};
+
+/// What the processes are grouped by in a [`GroupList`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    User,
+    /// The process group, e.g. all processes of a shell pipeline
+    ProcessGroup,
+    Session,
+}
+
+impl GroupBy {
+    /// The grouping after this one, or `None` to go back to the process list
+    pub fn next(self) -> Option<GroupBy> {
+        match self {
+            GroupBy::User => Some(GroupBy::ProcessGroup),
+            GroupBy::ProcessGroup => Some(GroupBy::Session),
+            GroupBy::Session => None,
+        }
+    }
+
+    pub fn header(&self) -> &'static str {
+        match self {
+            GroupBy::User => "User",
+            GroupBy::ProcessGroup => "PGID",
+            GroupBy::Session => "Session",
+        }
+    }
+
+    /// Description for the status line
+    pub fn title(&self) -> &'static str {
+        match self {
+            GroupBy::User => "Per user",
+            GroupBy::ProcessGroup => "Per process group",
+            GroupBy::Session => "Per session",
+        }
+    }
+
+    fn id(&self, process: &ProcessInfo) -> Option<i32> {
+        match self {
+            GroupBy::User => process.uid.map(|uid| uid as i32),
+            GroupBy::ProcessGroup => Some(process.pgrp),
+            GroupBy::Session => Some(process.session),
+        }
+    }
+}
+
+/// Column of a [`GroupList`] to sort by, which the view has separately from the sort
+/// of the process list
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GroupSortKey {
+    /// The user name, or the name of the group or session leader
+    Name,
+    /// Number of processes in the group
+    Count,
+    #[default]
+    Cpu,
+    Res,
+}
+
+impl GroupSortKey {
+    /// Order `a` before `b` if it should be shown above it in the key's default direction
+    fn compare(&self, a: &GroupSummary, b: &GroupSummary) -> Ordering {
+        match self {
+            GroupSortKey::Name => a.name.cmp(&b.name),
+            GroupSortKey::Count => b.processes.cmp(&a.processes),
+            GroupSortKey::Cpu => b.cpu_usage.total_cmp(&a.cpu_usage),
+            GroupSortKey::Res => b.memory.cmp(&a.memory),
+        }
+    }
+}
+
+impl Key for GroupSortKey {
+    const ALL: &'static [GroupSortKey] = &[
+        GroupSortKey::Name,
+        GroupSortKey::Count,
+        GroupSortKey::Cpu,
+        GroupSortKey::Res,
+    ];
+
+    fn header(&self) -> &'static str {
+        match self {
+            GroupSortKey::Name => "Name",
+            GroupSortKey::Count => "Procs",
+            GroupSortKey::Cpu => "CPU%",
+            GroupSortKey::Res => "Res",
+        }
+    }
+
+    fn default_direction(&self) -> Direction {
+        match self {
+            GroupSortKey::Name => Direction::Ascending,
+            _ => Direction::Descending,
+        }
+    }
+}
+
+/// Resource usage of all processes in a single group
+#[derive(Debug, Default, PartialEq)]
+pub struct GroupSummary {
+    /// The uid, process group id or session id
+    pub id: Option<i32>,
+    /// The user name, or the name of the group or session leader
+    pub name: String,
+    pub processes: usize,
+    pub cpu_usage: f32,
+    pub memory: usize,
+}
+
+/// Group `processes` by `group_by`. Threads are counted towards the CPU usage, since with
+/// threads shown each row only has the usage of one thread, but not towards the process
+/// count and memory, which they share with their process.
+pub fn summarize<'a>(
+    processes: impl IntoIterator<Item = &'a ProcessInfo> + Clone,
+    group_by: GroupBy,
+    users: &mut UserCache,
+) -> Vec<GroupSummary> {
+    // Groups and sessions are named after their leader, whose pid is the id
+    let leaders: HashMap<i32, &str> = processes
+        .clone()
+        .into_iter()
+        .filter(|process| process.process_type != ProcessType::Thread)
+        .map(|process| (process.pid, process.name.as_str()))
+        .collect();
+    let mut summaries: HashMap<Option<i32>, GroupSummary> = HashMap::new();
+
+    for process in processes {
+        let id = group_by.id(process);
+        let summary = summaries.entry(id).or_insert_with(|| GroupSummary {
+            id,
+            name: match (group_by, id) {
+                (GroupBy::User, Some(uid)) => users.get(uid as u32).to_string(),
+                (_, Some(id)) => leaders.get(&id).copied().unwrap_or_default().to_string(),
+                (_, None) => String::default(),
+            },
+            ..GroupSummary::default()
+        });
+
+        summary.cpu_usage += process.cpu_usage.unwrap_or_default();
+        if process.process_type != ProcessType::Thread {
+            summary.processes += 1;
+            summary.memory += process.memory;
+        }
+    }
+
+    summaries.into_values().collect()
+}
+
+/// Order `a` before `b` if it should be shown above it, with ties broken by name and id
+/// so that the order stays the same between refreshes
+fn compare(sort: SortSpec<GroupSortKey>, a: &GroupSummary, b: &GroupSummary) -> Ordering {
+    sort.directed(sort.key.compare(a, b))
+        .then_with(|| a.name.cmp(&b.name))
+        .then_with(|| a.id.cmp(&b.id))
+}
+
+/// The process list collapsed into one row per group
+pub struct GroupList<'a> {
+    current_data: &'a System,
+    group_by: GroupBy,
+    show_kernel_threads: bool,
+    sort: SortSpec<GroupSortKey>,
+    memory_unit: MemoryUnit,
+    precision: Precision,
+    theme: &'a Theme,
+}
+
+impl<'a> GroupList<'a> {
+    pub fn new(data: &'a System, group_by: GroupBy, theme: &'a Theme) -> Self {
+        GroupList {
+            current_data: data,
+            group_by,
+            show_kernel_threads: false,
+            sort: SortSpec::default(),
+            memory_unit: MemoryUnit::Auto,
+            precision: Precision::default(),
+            theme,
+        }
+    }
+
+    pub fn show_kernel_threads(mut self, show: bool) -> Self {
+        self.show_kernel_threads = show;
+
+        self
+    }
+
+    pub fn sort(mut self, sort: SortSpec<GroupSortKey>) -> Self {
+        self.sort = sort;
+
+        self
+    }
+
+    pub fn memory_unit(mut self, unit: MemoryUnit) -> Self {
+        self.memory_unit = unit;
+
+        self
+    }
+
+    pub fn precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+
+        self
+    }
+
+    pub fn summaries(&self) -> Vec<GroupSummary> {
+        let processes = self.current_data.processes.iter().filter(|p| {
+            self.show_kernel_threads || !matches!(p.process_type, ProcessType::KernelThread)
+        });
+        let mut summaries = summarize(processes, self.group_by, &mut UserCache::default());
+        summaries.sort_by(|a, b| compare(self.sort, a, b));
+
+        summaries
+    }
+}
+
+impl<'a> StatefulWidget for &mut GroupList<'a> {
+    type State = TableState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut TableState)
+    where
+        Self: Sized,
+    {
+        let group_by = self.group_by;
+        let summaries = self.summaries();
+        let max_memory = summaries.iter().map(|s| s.memory).max().unwrap_or_default();
+        let rows: Vec<Row> = summaries
+            .into_iter()
+            .map(|summary| {
+                let group = match (group_by, summary.id) {
+                    (GroupBy::User, _) | (_, None) => summary.name,
+                    (_, Some(id)) => format!("{id:>7} {}", summary.name),
+                };
+                Row::new([
+                    Cell::from(group),
+                    Cell::from(format!("{:>6}", summary.processes)),
+                    Cell::from(cpu_value(Some(summary.cpu_usage), self.precision))
+                        .style(severity_style(summary.cpu_usage, self.theme)),
+                    Cell::from(
+                        Line::from(human_bytes_fixed_unit(
+                            summary.memory,
+                            self.memory_unit,
+                            true,
+                        ))
+                        .right_aligned(),
+                    ),
+                ])
+            })
+            .collect();
+
+        let group_width = match group_by {
+            GroupBy::User => 10,
+            _ => 24,
+        };
+        Table::new(
+            rows,
+            [
+                Constraint::Max(group_width),
+                Constraint::Length(6),
+                Constraint::Length(self.precision.width()),
+                Constraint::Length(memory_width(max_memory, self.memory_unit)),
+            ],
+        )
+        .column_spacing(1)
+        .header(
+            Row::new([group_by.header(), " Procs", "CPU%", "Res"]).style(Style::new().underlined()),
+        )
+        .row_highlight_style(self.theme.selection_style())
+        .render(area, buf, state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: i32, uid: Option<u32>, process_type: ProcessType) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            uid,
+            process_type,
+            ..ProcessInfo::default()
+        }
+    }
+
+    #[test]
+    fn summarize_by_user() {
+        let process = |pid, uid, process_type, cpu_usage, memory| ProcessInfo {
+            cpu_usage: Some(cpu_usage),
+            memory,
+            ..process(pid, uid, process_type)
+        };
+        let processes = [
+            process(1, Some(0), ProcessType::Task, 1.0, 100),
+            process(2, Some(0), ProcessType::Thread, 2.0, 100),
+            process(3, Some(0), ProcessType::Task, 3.0, 50),
+            process(4, None, ProcessType::Task, 4.0, 10),
+        ];
+
+        let mut summaries = summarize(&processes, GroupBy::User, &mut UserCache::default());
+        summaries.sort_by(|a, b| compare(SortSpec::new(GroupSortKey::Cpu), a, b));
+
+        assert_eq!(
+            summaries,
+            vec![
+                GroupSummary {
+                    id: Some(0),
+                    name: "root".to_string(),
+                    processes: 2,
+                    cpu_usage: 6.0,
+                    memory: 150,
+                },
+                GroupSummary {
+                    id: None,
+                    name: String::default(),
+                    processes: 1,
+                    cpu_usage: 4.0,
+                    memory: 10,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn process_groups_are_named_after_their_leader() {
+        let process = |pid, pgrp, name: &str| ProcessInfo {
+            pgrp,
+            name: name.to_string(),
+            memory: 10,
+            ..process(pid, Some(0), ProcessType::Task)
+        };
+        let processes = [
+            process(10, 10, "sh"),
+            process(11, 10, "sort"),
+            process(12, 10, "uniq"),
+            // The leader has exited
+            process(20, 15, "orphan"),
+        ];
+
+        let mut summaries = summarize(&processes, GroupBy::ProcessGroup, &mut UserCache::default());
+        summaries.sort_by(|a, b| {
+            compare(
+                SortSpec {
+                    key: GroupSortKey::Count,
+                    direction: Direction::Ascending,
+                },
+                a,
+                b,
+            )
+        });
+
+        let groups: Vec<_> = summaries
+            .iter()
+            .map(|s| (s.id, s.name.as_str(), s.processes, s.memory))
+            .collect();
+        assert_eq!(groups, [(Some(15), "", 1, 10), (Some(10), "sh", 3, 30)]);
+    }
+}
@@ -0,0 +1,122 @@
+use std::{collections::VecDeque, time::Duration};
+
+/// How far back the history graphs go unless configured otherwise
+pub const DEFAULT_HISTORY_DURATION: Duration = Duration::from_secs(180);
+/// Longest history that can be configured, a day
+pub const MAX_HISTORY_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The most recent values of something sampled at a fixed interval, oldest first. Once
+/// full, the oldest value is dropped for every new one.
+#[derive(Debug, Clone, Default)]
+pub struct History<T> {
+    values: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> History<T> {
+    /// Nothing is allocated up front, the values grow towards `capacity` as they are pushed
+    pub fn new(capacity: usize) -> Self {
+        History {
+            values: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Number of samples taken `interval` apart that cover `duration`
+    pub fn capacity_for(duration: Duration, interval: Duration) -> usize {
+        if interval.is_zero() {
+            return 1;
+        }
+
+        (duration.as_secs_f64() / interval.as_secs_f64())
+            .ceil()
+            .max(1.0) as usize
+    }
+
+    pub fn push(&mut self, value: T) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.values.len() == self.capacity {
+            self.values.pop_front();
+        }
+        self.values.push_back(value);
+    }
+
+    /// Change how many values are kept. When shrinking, the newest values are kept.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        let excess = self.values.len().saturating_sub(capacity);
+        self.values.drain(..excess);
+        self.capacity = capacity;
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The newest value
+    pub fn latest(&self) -> Option<&T> {
+        self.values.back()
+    }
+
+    /// The values from oldest to newest
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        self.values.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oldest_values_are_dropped() {
+        let mut history = History::new(3);
+        for i in 0..5 {
+            history.push(i);
+        }
+        assert_eq!(history.iter().copied().collect::<Vec<_>>(), [2, 3, 4]);
+        assert_eq!(history.latest(), Some(&4));
+
+        history.set_capacity(5);
+        history.push(5);
+        assert_eq!(history.iter().copied().collect::<Vec<_>>(), [2, 3, 4, 5]);
+
+        history.set_capacity(2);
+        assert_eq!(history.iter().copied().collect::<Vec<_>>(), [4, 5]);
+        history.push(6);
+        assert_eq!(history.iter().copied().collect::<Vec<_>>(), [5, 6]);
+
+        history.set_capacity(0);
+        history.push(7);
+        assert!(history.is_empty());
+    }
+
+    #[test]
+    fn capacity_covers_duration() {
+        let secs = Duration::from_secs;
+        let millis = Duration::from_millis;
+
+        assert_eq!(History::<f32>::capacity_for(secs(180), millis(1_500)), 120);
+        // A partial interval still needs a sample
+        assert_eq!(History::<f32>::capacity_for(secs(10), secs(3)), 4);
+        assert_eq!(History::<f32>::capacity_for(secs(0), secs(1)), 1);
+        assert_eq!(History::<f32>::capacity_for(secs(10), secs(0)), 1);
+    }
+
+    #[test]
+    fn huge_capacity_is_not_allocated_up_front() {
+        let mut history = History::new(usize::MAX);
+        history.push(1u64);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history.capacity(), usize::MAX);
+    }
+}
@@ -0,0 +1,48 @@
+//! Process and system information read from Linux `/proc`.
+//!
+//! This is the data layer behind the `fell` process viewer. The terminal UI lives in
+//! the binary and consumes this crate like any other user would.
+//!
+//! # Stability
+//!
+//! The following types are considered public API:
+//!
+//! - [`Proc`], the sampler that keeps the state needed to calculate CPU usage between calls
+//! - [`System`], [`ThreadCount`], [`StateCount`], [`LoadAvg`], [`CpuUsage`], [`EventRate`],
+//!   [`MemInfo`], [`SwapRate`], [`GpuInfo`], [`Container`] and [`ContainerRuntime`], a
+//!   single sample of the system
+//! - [`SystemDiff`] and [`ProcessDelta`], the difference between two samples
+//! - [`ProcessInfo`], [`ProcessType`], [`ProcessFlags`], [`State`] and [`SyscallRate`], a
+//!   single process or thread
+//! - [`ProcessDetails`], extra information read on demand for a single process
+//! - [`SystemSource`] and [`HostInfo`], the interface between the UI and a platform backend
+//! - [`Error`] and [`Result`]
+//!
+//! Anything not re-exported here, such as the individual `/proc` file parsers, is internal
+//! and may change at any time.
+//!
+//! # Platform support
+//!
+//! Only Linux is supported. The [`proc`] module is the Linux backend and is only built for
+//! Linux, so that a backend for another platform can be added next to it.
+
+#[cfg(not(target_os = "linux"))]
+compile_error!("fell reads process information from Linux /proc and only supports Linux");
+
+#[cfg(target_os = "linux")]
+pub mod proc;
+#[cfg(target_os = "linux")]
+mod source;
+
+#[cfg(target_os = "linux")]
+pub use proc::{
+    cgroup::{Container, ContainerRuntime},
+    details::ProcessDetails,
+    flags::ProcessFlags,
+    process_info::{ProcessInfo, ProcessType},
+    state::State,
+    CpuUsage, Error, EventRate, GpuInfo, LoadAvg, MemInfo, Proc, ProcessDelta, Result, StateCount,
+    SwapRate, SyscallRate, System, SystemDiff, ThreadCount,
+};
+#[cfg(target_os = "linux")]
+pub use source::{HostInfo, SystemSource};
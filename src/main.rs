@@ -1,16 +1,53 @@
-use std::sync::mpsc;
+use std::{
+    fs::File,
+    io::{self, IsTerminal},
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    time::Duration,
+};
 
+use anyhow::Context;
 use app::App;
+use args::Args;
+use clap::Parser;
+use config::Config;
 use crossterm::event::Event;
-use proc::System;
+use fell::{
+    proc::{self, System},
+    Proc, ProcessDetails,
+};
+use replay::Recorder;
+use saved_state::SavedState;
+use theme::no_color_env;
 
+mod alert;
 mod app;
+mod args;
+mod column;
+mod config;
+mod core_history_widget;
 mod cpu_info_widget;
+mod error_log;
 mod event;
-mod proc;
+mod gpu_widget;
+mod group_list;
+mod history;
+mod logger;
+mod meter_bar;
+mod process_detail_widget;
 mod process_list;
+mod replay;
+mod saved_state;
+mod sort_key;
+mod status;
 mod sysinfo_thread;
 mod system_info_widget;
+mod theme;
+mod top;
+mod tree;
 mod tui;
 mod utils;
 
@@ -18,15 +55,142 @@ pub enum Message {
     SysInfo(System),
     Event(Event),
     SendThreads(bool),
+    ReadFds(bool),
+    ReadIo(bool),
+    ReadGpuMemory(bool),
+    ReadSwap(bool),
+    ReadCaps(bool),
+    ReadEnviron(bool),
+    /// Send the details of this process with every sample, or stop sending them
+    WatchDetails(Option<i32>),
+    Details(i32, ProcessDetails),
+    /// Stop sampling while paused. Usage is calculated from scratch once resumed, so
+    /// the first sample after resuming has no CPU usage, just like the first one at
+    /// startup.
+    Pause(bool),
+    /// Reading a sample failed
+    Error(proc::Error),
+    Quit,
 }
 
 fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    if let Some(path) = &args.log {
+        logger::init(path)?;
+    }
+    let mut config = Config::load(args.config.as_deref())?;
+    config.apply_args(&args);
+
+    if no_color_env() {
+        config.theme.color = false;
+    }
+
+    if let Some(count) = args.top {
+        let summary = top::top_summary(
+            &mut Proc::new().root(args.proc_root),
+            count,
+            args.delay.unwrap_or(top::DEFAULT_DELAY),
+            config.memory_unit,
+            config.percent_decimals,
+        )?;
+        print!("{summary}");
+        return Ok(());
+    }
+
+    // A full screen UI makes no sense when the output goes to a pipe or a file, print the
+    // status line instead
+    if args.status.is_some() || !io::stdout().is_terminal() {
+        let format = args.status.flatten().unwrap_or(config.status_format);
+        let line = status::status_line(
+            &mut Proc::new().root(args.proc_root),
+            &format,
+            config.memory_unit,
+        )?;
+        println!("{line}");
+        return Ok(());
+    }
+
+    let recorder = match &args.record {
+        Some(path) => Some(Recorder::new(File::create(path).with_context(|| {
+            format!("Failed to create recording {}", path.display())
+        })?)),
+        None => None,
+    };
+    let replay = match &args.replay {
+        Some(path) => Some(
+            File::open(path)
+                .with_context(|| format!("Failed to open recording {}", path.display()))?,
+        ),
+        None => None,
+    };
+
+    let state_path = saved_state::default_path();
+    let mut saved_state = state_path
+        .as_deref()
+        .map(SavedState::load)
+        .unwrap_or_default();
+    // A mode given on the command line is only for this run, but still wins
+    let name_mode = args
+        .name_mode
+        .or(saved_state.name_mode)
+        .unwrap_or(config.name_mode);
+
     let mut terminal = tui::init()?;
     let (thread_tx, thread_rx) = mpsc::channel::<Message>();
     let (main_tx, main_rx) = mpsc::channel::<Message>();
-    sysinfo_thread::start_thread(thread_tx.clone(), main_rx)?;
-    event::start_thread(thread_tx)?;
-    let app_result = App::new(false, true).run(&mut terminal, thread_rx, main_tx);
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let sysinfo_thread = match replay {
+        Some(file) => replay::start_thread(thread_tx.clone(), main_rx, file)?,
+        None => sysinfo_thread::start_thread(
+            thread_tx.clone(),
+            main_rx,
+            Proc::new()
+                .root(args.proc_root)
+                .smooth_cpu(config.cpu_smoothing),
+            recorder,
+            !args.no_threads,
+        )?,
+    };
+    let event_thread = event::start_thread(thread_tx, shutdown.clone())?;
+    let mut app = App::new(args.kernel_threads, !args.no_threads)
+        .sort_by(args.sort)
+        .filter(args.filter.unwrap_or_default())
+        .columns(config.columns)
+        .theme(config.theme)
+        .alerts(config.alerts)
+        .name_mode(name_mode)
+        .max_name_width(config.max_name_width)
+        .show_cores(!config.hide_cores)
+        .tree(config.tree)
+        .flash_state_changes(config.flash_state_changes)
+        .confirm_quit(config.confirm_quit)
+        .keep_frame(config.keep_frame)
+        .memory_unit(config.memory_unit)
+        .precision(config.percent_decimals)
+        .history_duration(Duration::from_secs(config.history_seconds))
+        .watch_pid(args.watch_pid, args.watch_exit_code);
+    let app_result = app.run(&mut terminal, thread_rx, main_tx.clone());
+
+    let _ = main_tx.send(Message::Quit);
+    shutdown.store(true, Ordering::Relaxed);
+    let _ = sysinfo_thread.join();
+    let _ = event_thread.join();
     tui::restore()?;
-    Ok(app_result?)
+    app_result?;
+    if app.current_name_mode() != name_mode {
+        saved_state.name_mode = Some(app.current_name_mode());
+        if let Some(path) = &state_path {
+            if let Err(err) = saved_state.save(path) {
+                log::warn!("{err:#}");
+            }
+        }
+    }
+    if let Some(frame) = app.last_frame() {
+        tui::print_frame(frame)?;
+    }
+    if let Some(code) = app.exit_code() {
+        process::exit(code);
+    }
+
+    Ok(())
 }
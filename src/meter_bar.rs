@@ -0,0 +1,75 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Style, Styled, Stylize},
+    text::Line,
+    widgets::Widget,
+};
+
+use crate::utils::Precision;
+
+pub struct MeterBar {
+    percent: f32,
+    title: String,
+    style: Style,
+    precision: Precision,
+}
+
+impl MeterBar {
+    pub fn new(percent: f32) -> Self {
+        MeterBar {
+            percent,
+            title: String::default(),
+            style: Style::default(),
+            precision: Precision::default(),
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+
+        self
+    }
+
+    pub fn precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+
+        self
+    }
+}
+
+impl Widget for &mut MeterBar {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let value = self.precision.percent(self.percent);
+        let title = if self.title.is_empty() {
+            String::default()
+        } else {
+            format!("{} ", self.title)
+        };
+        // Room left for the bar itself between the brackets
+        let bar_width = (area.width as usize)
+            .saturating_sub(title.len() + value.len() + 2)
+            .max(1);
+        let filled = ((self.percent.clamp(0.0, 100.0) / 100.0) * bar_width as f32).round() as usize;
+
+        Line::default()
+            .spans(vec![
+                title.into(),
+                "[".into(),
+                "|".repeat(filled).set_style(self.style),
+                " ".repeat(bar_width - filled).into(),
+                value.set_style(Style::default().bold()),
+                "]".into(),
+            ])
+            .render(area, buf);
+    }
+}
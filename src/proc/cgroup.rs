@@ -0,0 +1,145 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Container runtime a process was started by, as told by its cgroup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ContainerRuntime {
+    Docker,
+    Podman,
+    Lxc,
+    Kubernetes,
+}
+
+impl fmt::Display for ContainerRuntime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ContainerRuntime::Docker => "docker",
+            ContainerRuntime::Podman => "podman",
+            ContainerRuntime::Lxc => "lxc",
+            ContainerRuntime::Kubernetes => "kubernetes",
+        })
+    }
+}
+
+/// The container a process runs in
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Container {
+    pub runtime: ContainerRuntime,
+    /// Id of the container, or its name for LXC
+    pub id: String,
+}
+
+/// The container of a process from the contents of its `cgroup` file, `None` when it
+/// doesn't run in a container
+pub(super) fn parse_cgroup(input: &str) -> Option<Container> {
+    input
+        .lines()
+        .filter_map(|line| line.splitn(3, ':').nth(2))
+        .find_map(classify)
+}
+
+/// Classify a cgroup path like `/system.slice/docker-<id>.scope` by the runtime that
+/// created it
+pub fn classify(path: &str) -> Option<Container> {
+    let components: Vec<&str> = path
+        .split('/')
+        .filter(|component| !component.is_empty())
+        .map(|component| component.strip_suffix(".scope").unwrap_or(component))
+        .collect();
+    let container = |runtime, id: &str| {
+        Some(Container {
+            runtime,
+            id: id.to_string(),
+        })
+    };
+
+    if components.iter().any(|c| c.starts_with("kubepods")) {
+        // The pod cgroups, e.g. kubepods-besteffort-pod<uid>.slice, only hold containers
+        let last = components.last()?;
+        if last.ends_with(".slice") || last.starts_with("pod") || last.contains("conmon") {
+            return None;
+        }
+        let id = ["cri-containerd-", "crio-", "docker-"]
+            .iter()
+            .find_map(|prefix| last.strip_prefix(prefix))
+            .unwrap_or(last);
+        return container(ContainerRuntime::Kubernetes, id);
+    }
+
+    for (i, component) in components.iter().enumerate() {
+        let parent = i.checked_sub(1).map(|i| components[i]);
+        if let Some(id) = component.strip_prefix("docker-") {
+            return container(ContainerRuntime::Docker, id);
+        }
+        if let Some(id) = component.strip_prefix("libpod-") {
+            // conmon monitors the container from outside of it
+            if id.starts_with("conmon-") {
+                return None;
+            }
+            return container(ContainerRuntime::Podman, id);
+        }
+        if let Some(name) = component.strip_prefix("lxc.payload.") {
+            return container(ContainerRuntime::Lxc, name);
+        }
+        match parent {
+            Some("docker") => return container(ContainerRuntime::Docker, component),
+            Some("lxc" | "lxc.payload") => return container(ContainerRuntime::Lxc, component),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_runtimes() {
+        let runtime = |path| classify(path).map(|container| container.runtime);
+
+        assert_eq!(runtime("/docker/0123abcd"), Some(ContainerRuntime::Docker));
+        assert_eq!(
+            classify("/system.slice/docker-0123abcd.scope"),
+            Some(Container {
+                runtime: ContainerRuntime::Docker,
+                id: "0123abcd".to_string()
+            })
+        );
+        assert_eq!(
+            runtime("/machine.slice/libpod-0123abcd.scope/container"),
+            Some(ContainerRuntime::Podman)
+        );
+        assert_eq!(runtime("/machine.slice/libpod-conmon-0123abcd.scope"), None);
+        assert_eq!(
+            runtime("/lxc.payload.web/system.slice"),
+            Some(ContainerRuntime::Lxc)
+        );
+        assert_eq!(runtime("/lxc/web"), Some(ContainerRuntime::Lxc));
+        assert_eq!(
+            classify(
+                "/kubepods.slice/kubepods-burstable.slice/kubepods-burstable-pod12.slice/\
+                 cri-containerd-0123abcd.scope"
+            ),
+            Some(Container {
+                runtime: ContainerRuntime::Kubernetes,
+                id: "0123abcd".to_string()
+            })
+        );
+        assert_eq!(
+            runtime("/kubepods/burstable/pod12/0123abcd"),
+            Some(ContainerRuntime::Kubernetes)
+        );
+        assert_eq!(runtime("/kubepods/burstable/pod12"), None);
+        assert_eq!(runtime("/user.slice/user-1000.slice/session-2.scope"), None);
+        assert_eq!(runtime("/"), None);
+    }
+
+    #[test]
+    fn cgroup_file_without_container() {
+        assert_eq!(parse_cgroup("0::/init.scope\n"), None);
+        assert_eq!(parse_cgroup(""), None);
+    }
+}
@@ -1,5 +1,8 @@
+use std::str::FromStr;
+
 use pest::{iterators::Pair, Parser};
 use pest_derive::Parser;
+use serde::{Deserialize, Serialize};
 
 use super::{Error, Result};
 
@@ -7,23 +10,118 @@ use super::{Error, Result};
 #[grammar = "proc/cputime.pest"]
 struct CpuTimeParser;
 
-pub(super) fn parse_cpu_times(input: &str) -> Result<Vec<CpuTime>> {
-    let mut file = CpuTimeParser::parse(Rule::file, input)
-        .map_err(|_| Error::CpuTime("Failed to parse CPU time".to_string()))?;
-    let mut ret = Vec::default();
+/// The contents of `/proc/stat` that are used
+#[derive(Debug, Default)]
+pub(super) struct CpuStat {
+    pub(super) cpu_times: Vec<CpuTime>,
+    pub(super) counters: StatCounters,
+}
+
+/// Event counters since boot
+#[derive(Debug, Default, Clone, Copy)]
+pub(super) struct StatCounters {
+    interrupts: u64,
+    context_switches: u64,
+}
+
+impl FromStr for CpuStat {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let mut file = CpuTimeParser::parse(Rule::file, input).map_err(|_| Error::CpuTime {
+            message: "Failed to parse CPU time".to_string(),
+            input: input.to_string(),
+        })?;
+        let mut ret = CpuStat::default();
+
+        if let Some(file) = file.next() {
+            for line in file.into_inner() {
+                match line.as_rule() {
+                    Rule::intr => ret.counters.interrupts = parse_counter(line, "intr")?,
+                    Rule::ctxt => ret.counters.context_switches = parse_counter(line, "ctxt")?,
+                    _ => ret.cpu_times.push(CpuTime::from_pair(line)?),
+                }
+            }
+        }
+
+        Ok(ret)
+    }
+}
+
+/// Parse the first number of a counter line, which for `intr` is the sum of the rest
+fn parse_counter(line: Pair<Rule>, name: &str) -> Result<u64> {
+    let err = |message: &str| Error::CpuTime {
+        message: message.to_string(),
+        input: line.as_str().to_string(),
+    };
+    line.clone()
+        .into_inner()
+        .next()
+        .ok_or_else(|| err(&format!("Failed to read {name}")))?
+        .as_str()
+        .parse()
+        .map_err(|_| err(&format!("Failed to parse {name}")))
+}
+
+/// Events per second since the previous sample
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EventRate {
+    pub interrupts: f32,
+    pub context_switches: f32,
+}
 
-    if let Some(file) = file.next() {
-        for line in file.into_inner() {
-            let cpu_time = CpuTime::from_pair(line)?;
-            ret.push(cpu_time);
+impl StatCounters {
+    /// Rate of events since `prev`, which was read `elapsed` seconds earlier
+    pub(super) fn rate(&self, prev: &StatCounters, elapsed: f64) -> EventRate {
+        if elapsed <= 0.0 {
+            return EventRate::default();
+        }
+
+        let rate = |new: u64, old: u64| (new.saturating_sub(old) as f64 / elapsed) as f32;
+
+        EventRate {
+            interrupts: rate(self.interrupts, prev.interrupts),
+            context_switches: rate(self.context_switches, prev.context_switches),
         }
     }
+}
+
+/// CPU usage in percent since the previous sample, smoothed over the previous samples
+/// when enabled with [`Proc::smooth_cpu`]
+///
+/// [`Proc::smooth_cpu`]: super::Proc::smooth_cpu
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct CpuUsage {
+    /// Usage across all CPUs
+    pub total: f32,
+    /// Usage per online CPU, labeled with the CPU number
+    pub cores: Vec<(u32, f32)>,
+}
 
-    Ok(ret)
+/// Calculate usage by matching up CPUs by their number rather than position, since CPUs
+/// can go offline or come online between samples. CPUs without a previous sample are
+/// left out until the next one.
+pub(super) fn cpu_usage(new: &[CpuTime], old: &[CpuTime]) -> Option<CpuUsage> {
+    let find_old = |cpu: Option<u32>| old.iter().find(|old| old.cpu == cpu);
+    let total = new
+        .iter()
+        .find(|new| new.cpu.is_none())
+        .and_then(|new| Some(new.cpu_usage(find_old(None)?)))?;
+    let cores = new
+        .iter()
+        .filter_map(|new| {
+            let cpu = new.cpu?;
+            Some((cpu, new.cpu_usage(find_old(Some(cpu))?)))
+        })
+        .collect();
+
+    Some(CpuUsage { total, cores })
 }
 
 #[derive(Debug, Default)]
 pub(super) struct CpuTime {
+    /// The CPU number, or `None` for the line summing all CPUs
+    pub(super) cpu: Option<u32>,
     pub(super) user: u64,
     pub(super) nice: u64,
     pub(super) system: u64,
@@ -36,6 +134,23 @@ pub(super) struct CpuTime {
     pub(super) guest_nice: u64,
 }
 
+impl FromStr for CpuTime {
+    type Err = Error;
+
+    /// Parse a single `cpu` line of `/proc/stat`
+    fn from_str(input: &str) -> Result<Self> {
+        let line = CpuTimeParser::parse(Rule::line, input)
+            .ok()
+            .and_then(|mut pairs| pairs.next())
+            .ok_or_else(|| Error::CpuTime {
+                message: "Failed to parse CPU line".to_string(),
+                input: input.to_string(),
+            })?;
+
+        CpuTime::from_pair(line)
+    }
+}
+
 impl CpuTime {
     fn work(&self) -> u64 {
         self.user
@@ -57,74 +172,94 @@ impl CpuTime {
     }
 
     pub(super) fn cpu_usage(&self, old: &CpuTime) -> f32 {
-        (self.work() - old.work()) as f32 * 100.0 / (self.total() - old.total()) as f32
+        let total = self.total().saturating_sub(old.total());
+        if total == 0 {
+            return 0.0;
+        }
+
+        self.work().saturating_sub(old.work()) as f32 * 100.0 / total as f32
     }
 
     fn from_pair(value: Pair<Rule>) -> Result<Self> {
-        let mut fields = value.into_inner();
-        fields.next().unwrap();
+        let err = |message: &str| Error::CpuTime {
+            message: message.to_string(),
+            input: value.as_str().to_string(),
+        };
+        let mut fields = value.clone().into_inner();
+        let cpu = fields
+            .next()
+            .ok_or_else(|| err("Failed to read CPU name"))?
+            .as_str()
+            .trim()
+            .trim_start_matches("cpu");
+        let cpu = if cpu.is_empty() {
+            None
+        } else {
+            Some(cpu.parse().map_err(|_| err("Failed to parse CPU number"))?)
+        };
         let user: u64 = fields
             .next()
-            .ok_or_else(|| Error::CpuTime("Failed to read user time".to_string()))?
+            .ok_or_else(|| err("Failed to read user time"))?
             .as_str()
             .parse()
-            .map_err(|_| Error::CpuTime("Failed to parse user time".to_string()))?;
+            .map_err(|_| err("Failed to parse user time"))?;
         let nice: u64 = fields
             .next()
-            .ok_or_else(|| Error::CpuTime("Failed to read nice time".to_string()))?
+            .ok_or_else(|| err("Failed to read nice time"))?
             .as_str()
             .parse()
-            .map_err(|_| Error::CpuTime("Failed to parse nice time".to_string()))?;
+            .map_err(|_| err("Failed to parse nice time"))?;
         let system: u64 = fields
             .next()
-            .ok_or_else(|| Error::CpuTime("Failed to read system time".to_string()))?
+            .ok_or_else(|| err("Failed to read system time"))?
             .as_str()
             .parse()
-            .map_err(|_| Error::CpuTime("Failed to parse system time".to_string()))?;
+            .map_err(|_| err("Failed to parse system time"))?;
         let idle: u64 = fields
             .next()
-            .ok_or_else(|| Error::CpuTime("Failed to read idle time".to_string()))?
+            .ok_or_else(|| err("Failed to read idle time"))?
             .as_str()
             .parse()
-            .map_err(|_| Error::CpuTime("Failed to parse idle time".to_string()))?;
+            .map_err(|_| err("Failed to parse idle time"))?;
         let iowait: u64 = fields
             .next()
-            .ok_or_else(|| Error::CpuTime("Failed to read iowait time".to_string()))?
+            .ok_or_else(|| err("Failed to read iowait time"))?
             .as_str()
             .parse()
-            .map_err(|_| Error::CpuTime("Failed to parse iowait time".to_string()))?;
+            .map_err(|_| err("Failed to parse iowait time"))?;
         let irq: u64 = fields
             .next()
-            .ok_or_else(|| Error::CpuTime("Failed to read irq time".to_string()))?
+            .ok_or_else(|| err("Failed to read irq time"))?
             .as_str()
             .parse()
-            .map_err(|_| Error::CpuTime("Failed to parse irq time".to_string()))?;
+            .map_err(|_| err("Failed to parse irq time"))?;
         let softirq: u64 = fields
             .next()
-            .ok_or_else(|| Error::CpuTime("Failed to read softirq time".to_string()))?
+            .ok_or_else(|| err("Failed to read softirq time"))?
             .as_str()
             .parse()
-            .map_err(|_| Error::CpuTime("Failed to parse softirq time".to_string()))?;
+            .map_err(|_| err("Failed to parse softirq time"))?;
         let steal: u64 = fields
             .next()
-            .ok_or_else(|| Error::CpuTime("Failed to read steal time".to_string()))?
+            .ok_or_else(|| err("Failed to read steal time"))?
             .as_str()
             .parse()
-            .map_err(|_| Error::CpuTime("Failed to parse steal time".to_string()))?;
+            .map_err(|_| err("Failed to parse steal time"))?;
         let guest: u64 = fields
             .next()
-            .ok_or_else(|| Error::CpuTime("Failed to read guest time".to_string()))?
+            .ok_or_else(|| err("Failed to read guest time"))?
             .as_str()
             .parse()
-            .map_err(|_| Error::CpuTime("Failed to parse guest time".to_string()))?;
+            .map_err(|_| err("Failed to parse guest time"))?;
         let guest_nice: u64 = fields
             .next()
-            .ok_or_else(|| Error::CpuTime("Failed to read guest_nice time".to_string()))?
+            .ok_or_else(|| err("Failed to read guest_nice time"))?
             .as_str()
             .parse()
-            .map_err(|_| Error::CpuTime("Failed to parse guest_nice time".to_string()))?;
+            .map_err(|_| err("Failed to parse guest_nice time"))?;
 
         Ok(CpuTime {
+            cpu,
             user,
             nice,
             system,
@@ -138,3 +273,63 @@ impl CpuTime {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpus_going_offline_and_online() -> Result<()> {
+        let old = CpuStat::from_str(
+            "cpu  300 0 0 300 0 0 0 0 0 0\n\
+             cpu0 100 0 0 100 0 0 0 0 0 0\n\
+             cpu1 100 0 0 100 0 0 0 0 0 0\n\
+             cpu2 100 0 0 100 0 0 0 0 0 0\n",
+        )?;
+        let new = CpuStat::from_str(
+            "cpu  400 0 0 400 0 0 0 0 0 0\n\
+             cpu0 200 0 0 100 0 0 0 0 0 0\n\
+             cpu2 100 0 0 200 0 0 0 0 0 0\n\
+             cpu3 100 0 0 100 0 0 0 0 0 0\n",
+        )?;
+
+        let usage = cpu_usage(&new.cpu_times, &old.cpu_times).unwrap();
+        assert_eq!(usage.total, 50.0);
+        assert_eq!(usage.cores, vec![(0, 100.0), (2, 0.0)]);
+
+        assert!(cpu_usage(&new.cpu_times, &[]).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn interrupt_and_context_switch_rate() -> Result<()> {
+        let old = CpuStat::from_str(
+            "cpu  300 0 0 300 0 0 0 0 0 0\n\
+             cpu0 300 0 0 300 0 0 0 0 0 0\n\
+             intr 1000 600 400 0 0\n\
+             ctxt 5000\n\
+             btime 1700000000\n\
+             processes 1234\n",
+        )?;
+        let new = CpuStat::from_str(
+            "cpu  400 0 0 400 0 0 0 0 0 0\n\
+             cpu0 400 0 0 400 0 0 0 0 0 0\n\
+             intr 1400 800 600 0 0\n\
+             ctxt 6000\n\
+             btime 1700000000\n\
+             processes 1240\n",
+        )?;
+
+        assert_eq!(new.cpu_times.len(), 2);
+        assert_eq!(
+            new.counters.rate(&old.counters, 2.0),
+            EventRate {
+                interrupts: 200.0,
+                context_switches: 500.0,
+            }
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Extra information about a single process that is too expensive to read for every
+/// process on every sample, so it is only read on demand.
+#[derive(Debug)]
+pub struct ProcessDetails {
+    /// The kernel function the process is waiting in, if it is blocked
+    pub wchan: Option<String>,
+    /// The current working directory. Reading it fails for other users' processes.
+    pub cwd: io::Result<PathBuf>,
+    /// The path of the running executable. Reading it fails for other users' processes
+    /// and kernel threads.
+    pub exe: io::Result<PathBuf>,
+}
+
+impl ProcessDetails {
+    /// Read the details of `pid` from the proc mounted at `root`
+    pub fn read(root: &Path, pid: i32) -> Self {
+        let path = root.join(pid.to_string());
+
+        ProcessDetails {
+            wchan: read_wchan(&path),
+            cwd: fs::read_link(path.join("cwd")),
+            exe: fs::read_link(path.join("exe")),
+        }
+    }
+}
+
+/// Read the `KEY=value` environment variables the process at `path` was started with.
+/// This fails with permission denied for other users' processes.
+pub(super) fn read_environ(path: &Path) -> io::Result<Vec<String>> {
+    let environ = fs::read(path.join("environ"))?;
+
+    Ok(environ
+        .split(|b| *b == 0)
+        .filter(|var| !var.is_empty())
+        .map(|var| String::from_utf8_lossy(var).into_owned())
+        .collect())
+}
+
+fn read_wchan(path: &Path) -> Option<String> {
+    // Reads "0" when the process isn't blocked and fails for other users' processes
+    let wchan = fs::read_to_string(path.join("wchan")).ok()?;
+    let wchan = wchan.trim();
+
+    if wchan.is_empty() || wchan == "0" {
+        None
+    } else {
+        Some(wchan.to_string())
+    }
+}
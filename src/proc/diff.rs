@@ -0,0 +1,165 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{process_info::ProcessInfo, state::State, System};
+
+/// Difference between two samples of the system, see [`System::diff`]
+#[derive(Default, Debug, PartialEq)]
+pub struct SystemDiff {
+    /// Processes only in the newer sample
+    pub added: Vec<i32>,
+    /// Processes only in the older sample
+    pub removed: Vec<i32>,
+    /// Change of the processes in both samples
+    pub changed: Vec<ProcessDelta>,
+}
+
+/// Change of a single process between two samples
+#[derive(Default, Debug, PartialEq)]
+pub struct ProcessDelta {
+    pub pid: i32,
+    /// Change in CPU usage in percentage points. Zero when either sample lacks a CPU usage.
+    pub cpu_usage: f32,
+    /// Change in resident memory in bytes
+    pub memory: i64,
+    /// The state in the older sample, if the process has changed state since
+    pub prev_state: Option<State>,
+}
+
+impl System {
+    /// Compare this sample to an older one.
+    ///
+    /// A PID that was reused by a new process in between counts as both removed and added.
+    /// The lists are sorted by PID.
+    pub fn diff(&self, prev: &System) -> SystemDiff {
+        let prev_processes: HashMap<i32, &ProcessInfo> =
+            prev.processes.iter().map(|p| (p.pid, p)).collect();
+        let mut diff = SystemDiff::default();
+
+        for process in &self.processes {
+            match prev_processes.get(&process.pid) {
+                Some(old) if old.start_time == process.start_time => {
+                    let cpu_usage = match (process.cpu_usage, old.cpu_usage) {
+                        (Some(new), Some(old)) => new - old,
+                        _ => 0.0,
+                    };
+                    diff.changed.push(ProcessDelta {
+                        pid: process.pid,
+                        cpu_usage,
+                        memory: process.memory as i64 - old.memory as i64,
+                        prev_state: (old.state != process.state).then(|| old.state.clone()),
+                    });
+                }
+                Some(_) => {
+                    diff.removed.push(process.pid);
+                    diff.added.push(process.pid);
+                }
+                None => diff.added.push(process.pid),
+            }
+        }
+
+        let pids: HashSet<i32> = self.processes.iter().map(|p| p.pid).collect();
+        diff.removed.extend(
+            prev.processes
+                .iter()
+                .map(|p| p.pid)
+                .filter(|pid| !pids.contains(pid)),
+        );
+
+        diff.added.sort_unstable();
+        diff.removed.sort_unstable();
+        diff.changed.sort_unstable_by_key(|delta| delta.pid);
+
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: i32, start_time: u64, cpu_usage: f32, memory: usize) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            tgid: pid,
+            start_time,
+            cpu_usage: Some(cpu_usage),
+            memory,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn exited_process_is_removed() {
+        let prev = System {
+            processes: vec![process(1, 10, 1.0, 4096), process(2, 20, 5.0, 8192)],
+            ..Default::default()
+        };
+        let new = System {
+            processes: vec![process(1, 10, 3.5, 2048)],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            new.diff(&prev),
+            SystemDiff {
+                added: vec![],
+                removed: vec![2],
+                changed: vec![ProcessDelta {
+                    pid: 1,
+                    cpu_usage: 2.5,
+                    memory: -2048,
+                    prev_state: None,
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn reused_pid_is_removed_and_added() {
+        let prev = System {
+            processes: vec![process(7, 100, 1.0, 4096)],
+            ..Default::default()
+        };
+        let new = System {
+            processes: vec![process(7, 900, 1.0, 4096), process(8, 901, 0.0, 0)],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            new.diff(&prev),
+            SystemDiff {
+                added: vec![7, 8],
+                removed: vec![7],
+                changed: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn state_change_is_reported() {
+        let prev = System {
+            processes: vec![
+                ProcessInfo {
+                    state: State::Sleeping,
+                    ..process(1, 10, 0.0, 0)
+                },
+                process(2, 20, 0.0, 0),
+            ],
+            ..Default::default()
+        };
+        let new = System {
+            processes: vec![
+                ProcessInfo {
+                    state: State::Waiting,
+                    ..process(1, 10, 0.0, 0)
+                },
+                process(2, 20, 0.0, 0),
+            ],
+            ..Default::default()
+        };
+
+        let diff = new.diff(&prev);
+        assert_eq!(diff.changed[0].prev_state, Some(State::Sleeping));
+        assert_eq!(diff.changed[1].prev_state, None);
+    }
+}
@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+pub(super) struct PrevFaults {
+    uptime: f64,
+    major: u64,
+}
+
+pub(super) trait PrevFaultsMap {
+    /// Calculate the major page faults per second since the previous call for `pid`
+    fn calculate(&mut self, pid: i32, uptime: f64, major: u64) -> Option<f32>;
+    fn cleanup(&mut self, uptime: f64);
+}
+
+impl PrevFaultsMap for HashMap<i32, PrevFaults> {
+    fn calculate(&mut self, pid: i32, uptime: f64, major: u64) -> Option<f32> {
+        let prev = self.insert(pid, PrevFaults { uptime, major })?;
+        let elapsed = uptime - prev.uptime;
+        if elapsed <= 0.0 {
+            return Some(0.0);
+        }
+
+        Some((major.saturating_sub(prev.major) as f64 / elapsed) as f32)
+    }
+
+    fn cleanup(&mut self, uptime: f64) {
+        self.retain(|_, p| p.uptime.eq(&uptime));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn major_fault_rate_between_samples() {
+        let mut prev_faults = HashMap::default();
+
+        assert_eq!(prev_faults.calculate(1, 10.0, 100), None);
+        assert_eq!(prev_faults.calculate(1, 12.0, 130), Some(15.0));
+        // A reused pid starting from fewer faults isn't a negative rate
+        assert_eq!(prev_faults.calculate(1, 13.0, 5), Some(0.0));
+    }
+}
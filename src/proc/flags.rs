@@ -0,0 +1,49 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// The kernel's per-process `PF_*` flags from field 9 of `/proc/[pid]/stat`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessFlags(pub u64);
+
+impl ProcessFlags {
+    /// The process is exiting
+    pub const EXITING: u64 = 0x0000_0004;
+    /// The process is a kernel thread
+    pub const KTHREAD: u64 = 0x0020_0000;
+
+    const NAMES: [(u64, &'static str); 2] = [
+        (ProcessFlags::EXITING, "exiting"),
+        (ProcessFlags::KTHREAD, "kthread"),
+    ];
+
+    pub fn is_exiting(&self) -> bool {
+        self.0 & ProcessFlags::EXITING != 0
+    }
+
+    pub fn is_kernel_thread(&self) -> bool {
+        self.0 & ProcessFlags::KTHREAD != 0
+    }
+
+    /// Names of the notable flags that are set
+    pub fn names(&self) -> Vec<&'static str> {
+        ProcessFlags::NAMES
+            .iter()
+            .filter(|(flag, _)| self.0 & flag != 0)
+            .map(|(_, name)| *name)
+            .collect()
+    }
+}
+
+impl Display for ProcessFlags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#010x}", self.0)?;
+
+        let names = self.names();
+        if !names.is_empty() {
+            write!(f, " ({})", names.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
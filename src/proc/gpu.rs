@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Usage of a single GPU
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GpuInfo {
+    pub name: String,
+    /// Percent of the time a kernel was running on the GPU since the previous query
+    pub utilization: f32,
+    /// Used video memory in bytes
+    pub memory_used: u64,
+    /// Total video memory in bytes
+    pub memory_total: u64,
+}
+
+/// Reads GPU usage through NVML when built with the `gpu` feature
+#[cfg(feature = "gpu")]
+pub(super) struct GpuReader {
+    /// `None` when NVML or the driver isn't available
+    nvml: Option<nvml_wrapper::Nvml>,
+}
+
+#[cfg(feature = "gpu")]
+impl GpuReader {
+    pub(super) fn new() -> Self {
+        let nvml = nvml_wrapper::Nvml::init()
+            .inspect_err(|err| log::debug!("GPU usage not available: {err}"))
+            .ok();
+
+        GpuReader { nvml }
+    }
+
+    /// The usage of every GPU, or `None` without NVML. GPUs that fail to be read are
+    /// left out.
+    pub(super) fn read(&self) -> Option<Vec<GpuInfo>> {
+        let nvml = self.nvml.as_ref()?;
+        let count = nvml.device_count().ok()?;
+
+        Some(
+            (0..count)
+                .filter_map(|index| {
+                    let device = nvml.device_by_index(index).ok()?;
+                    let memory = device.memory_info().ok()?;
+
+                    Some(GpuInfo {
+                        name: device.name().unwrap_or_else(|_| format!("GPU {index}")),
+                        utilization: device
+                            .utilization_rates()
+                            .map(|rates| rates.gpu as f32)
+                            .unwrap_or_default(),
+                        memory_used: memory.used,
+                        memory_total: memory.total,
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// GPU memory used by each process running on any of the GPUs, by PID
+    pub(super) fn process_memory(&self) -> HashMap<i32, u64> {
+        use nvml_wrapper::enums::device::UsedGpuMemory;
+
+        let Some(nvml) = &self.nvml else {
+            return HashMap::new();
+        };
+
+        let devices = (0..nvml.device_count().unwrap_or_default()).filter_map(|index| {
+            let device = nvml.device_by_index(index).ok()?;
+            let processes = device
+                .running_compute_processes()
+                .into_iter()
+                .chain(device.running_graphics_processes())
+                .flatten()
+                .filter_map(|process| match process.used_gpu_memory {
+                    UsedGpuMemory::Used(used) => Some((process.pid as i32, used)),
+                    UsedGpuMemory::Unavailable => None,
+                })
+                .collect::<Vec<_>>();
+
+            Some(processes)
+        });
+
+        total_process_memory(devices)
+    }
+}
+
+/// GPU memory of each process summed over the devices, from the `(pid, used)` pairs
+/// each device lists. A process with both a compute and a graphics context is listed
+/// twice by its device, with the same memory both times, so it only counts once per
+/// device.
+#[cfg(any(feature = "gpu", test))]
+fn total_process_memory(devices: impl IntoIterator<Item = Vec<(i32, u64)>>) -> HashMap<i32, u64> {
+    let mut memory = HashMap::new();
+    for processes in devices {
+        let mut device_memory: HashMap<i32, u64> = HashMap::new();
+        for (pid, used) in processes {
+            let entry = device_memory.entry(pid).or_default();
+            *entry = (*entry).max(used);
+        }
+        for (pid, used) in device_memory {
+            *memory.entry(pid).or_default() += used;
+        }
+    }
+
+    memory
+}
+
+/// Stand-in for builds without the `gpu` feature, which never has any GPUs
+#[cfg(not(feature = "gpu"))]
+pub(super) struct GpuReader;
+
+#[cfg(not(feature = "gpu"))]
+impl GpuReader {
+    pub(super) fn new() -> Self {
+        GpuReader
+    }
+
+    pub(super) fn read(&self) -> Option<Vec<GpuInfo>> {
+        None
+    }
+
+    pub(super) fn process_memory(&self) -> HashMap<i32, u64> {
+        HashMap::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contexts_on_one_device_count_once() {
+        let memory = total_process_memory([
+            // Process 1 has a compute and a graphics context
+            vec![(1, 100), (2, 50), (1, 100)],
+            vec![(1, 30)],
+        ]);
+
+        assert_eq!(memory, HashMap::from([(1, 130), (2, 50)]));
+    }
+}
@@ -0,0 +1,109 @@
+use std::{collections::HashMap, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Error, Result};
+
+/// The counters used from `/proc/[pid]/io`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(super) struct IoCounters {
+    syscr: u64,
+    syscw: u64,
+}
+
+/// Read and write syscalls per second
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SyscallRate {
+    pub reads: f32,
+    pub writes: f32,
+}
+
+impl FromStr for IoCounters {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let err = |message: &str| Error::ProcessIo {
+            message: message.to_string(),
+            input: input.to_string(),
+        };
+        let mut syscr = None;
+        let mut syscw = None;
+
+        for line in input.lines() {
+            let Some((key, value)) = line.split_once(": ") else {
+                continue;
+            };
+            let counter = match key {
+                "syscr" => &mut syscr,
+                "syscw" => &mut syscw,
+                _ => continue,
+            };
+            *counter = Some(
+                value
+                    .trim()
+                    .parse()
+                    .map_err(|_| err(&format!("Failed to parse {key}")))?,
+            );
+        }
+
+        Ok(IoCounters {
+            syscr: syscr.ok_or_else(|| err("Missing syscr"))?,
+            syscw: syscw.ok_or_else(|| err("Missing syscw"))?,
+        })
+    }
+}
+
+pub(super) struct PrevIo {
+    uptime: f64,
+    counters: IoCounters,
+}
+
+pub(super) trait PrevIoMap {
+    /// Calculate the syscall rates since the previous call for `pid`
+    fn calculate(&mut self, pid: i32, uptime: f64, counters: IoCounters) -> Option<SyscallRate>;
+    fn cleanup(&mut self, uptime: f64);
+}
+
+impl PrevIoMap for HashMap<i32, PrevIo> {
+    fn calculate(&mut self, pid: i32, uptime: f64, counters: IoCounters) -> Option<SyscallRate> {
+        let prev = self.insert(pid, PrevIo { uptime, counters })?;
+        let elapsed = uptime - prev.uptime;
+        if elapsed <= 0.0 {
+            return Some(SyscallRate::default());
+        }
+
+        let rate = |new: u64, old: u64| (new.saturating_sub(old) as f64 / elapsed) as f32;
+
+        Some(SyscallRate {
+            reads: rate(counters.syscr, prev.counters.syscr),
+            writes: rate(counters.syscw, prev.counters.syscw),
+        })
+    }
+
+    fn cleanup(&mut self, uptime: f64) {
+        self.retain(|_, p| p.uptime.eq(&uptime));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn syscall_rate_between_samples() -> Result<()> {
+        let mut prev_io = HashMap::default();
+        let io = "rchar: 100\nwchar: 50\nsyscr: 10\nsyscw: 5\nread_bytes: 0\n";
+        assert_eq!(prev_io.calculate(1, 10.0, io.parse()?), None);
+
+        let io = "rchar: 300\nwchar: 50\nsyscr: 40\nsyscw: 5\nread_bytes: 0\n";
+        assert_eq!(
+            prev_io.calculate(1, 11.5, io.parse()?),
+            Some(SyscallRate {
+                reads: 20.0,
+                writes: 0.0,
+            })
+        );
+
+        Ok(())
+    }
+}
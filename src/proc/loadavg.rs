@@ -1,40 +1,59 @@
-use std::{fs, path::PathBuf};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
 
 use super::{Error, Result};
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct LoadAvg {
     pub one: f32,
     pub five: f32,
     pub fifteen: f32,
 }
 
-impl LoadAvg {
-    pub(super) fn load(path: PathBuf) -> Result<Self> {
-        let loadavg = fs::read_to_string(&path).map_err(|_| {
-            Error::LoadAvg(format!(
-                "Could not find a loadavg file at {}",
-                path.display()
-            ))
-        })?;
-        let mut loadavg = loadavg.split(' ');
+impl FromStr for LoadAvg {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let err = |message: &str| Error::LoadAvg {
+            message: message.to_string(),
+            input: input.to_string(),
+        };
+        let mut loadavg = input.split(' ');
 
         Ok(LoadAvg {
             one: loadavg
                 .next()
-                .ok_or_else(|| Error::LoadAvg("Failed to read 1 minute average".to_string()))?
+                .ok_or_else(|| err("Failed to read 1 minute average"))?
                 .parse()
-                .map_err(|_| Error::LoadAvg("Failed to parse 1 minute average".to_string()))?,
+                .map_err(|_| err("Failed to parse 1 minute average"))?,
             five: loadavg
                 .next()
-                .ok_or_else(|| Error::LoadAvg("Failed to read 5 minute average".to_string()))?
+                .ok_or_else(|| err("Failed to read 5 minute average"))?
                 .parse()
-                .map_err(|_| Error::LoadAvg("Failed to parse 5 minute average".to_string()))?,
+                .map_err(|_| err("Failed to parse 5 minute average"))?,
             fifteen: loadavg
                 .next()
-                .ok_or_else(|| Error::LoadAvg("Failed to read 15 minute average".to_string()))?
+                .ok_or_else(|| err("Failed to read 15 minute average"))?
                 .parse()
-                .map_err(|_| Error::LoadAvg("Failed to parse 15 minute average".to_string()))?,
+                .map_err(|_| err("Failed to parse 15 minute average"))?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_names_field_and_input() {
+        let input = "0.52 abc 0.20 1/123 4567";
+        let err = input.parse::<LoadAvg>().unwrap_err();
+
+        assert!(matches!(
+            &err,
+            Error::LoadAvg { message, input: raw }
+                if message == "Failed to parse 5 minute average" && raw == input
+        ));
+    }
+}
@@ -1,5 +1,8 @@
+use std::str::FromStr;
+
 use pest::Parser;
 use pest_derive::Parser;
+use serde::{Deserialize, Serialize};
 
 use super::{Error, Result};
 
@@ -7,7 +10,7 @@ use super::{Error, Result};
 #[grammar = "proc/meminfo.pest"]
 struct MemInfoParser;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct MemInfo {
     pub mem_total: usize,
     pub mem_free: usize,
@@ -15,56 +18,82 @@ pub struct MemInfo {
     pub swap_free: usize,
 }
 
-impl MemInfo {
-    pub fn parse(input: &str) -> Result<Self> {
-        let mut file = MemInfoParser::parse(Rule::file, input)
-            .map_err(|_| Error::MemInfo("Failed to parse meminfo".to_string()))?;
+impl FromStr for MemInfo {
+    type Err = Error;
 
-        let mut mem_total = 0;
-        let mut mem_free = 0;
-        let mut swap_total = 0;
-        let mut swap_free = 0;
+    fn from_str(input: &str) -> Result<Self> {
+        let err = |message: &str| Error::MemInfo {
+            message: message.to_string(),
+            input: input.to_string(),
+        };
+        let mut file =
+            MemInfoParser::parse(Rule::file, input).map_err(|_| err("Failed to parse meminfo"))?;
+
+        let mut mem_total: Option<usize> = None;
+        let mut mem_free: Option<usize> = None;
+        let mut swap_total: Option<usize> = None;
+        let mut swap_free: Option<usize> = None;
 
         if let Some(pairs) = file.next() {
             for pair in pairs.into_inner() {
                 match pair.as_rule() {
                     Rule::memtotal => {
-                        mem_total =
-                            pair.into_inner().as_str().parse().map_err(|_| {
-                                Error::MemInfo("Failed to parse MemTotal".to_string())
-                            })?
+                        mem_total = Some(
+                            pair.into_inner()
+                                .as_str()
+                                .parse()
+                                .map_err(|_| err("Failed to parse MemTotal"))?,
+                        )
                     }
                     Rule::memfree => {
-                        mem_free =
-                            pair.into_inner().as_str().parse().map_err(|_| {
-                                Error::MemInfo("Failed to parse MemFree".to_string())
-                            })?
+                        mem_free = Some(
+                            pair.into_inner()
+                                .as_str()
+                                .parse()
+                                .map_err(|_| err("Failed to parse MemFree"))?,
+                        )
                     }
                     Rule::swaptotal => {
-                        swap_total =
-                            pair.into_inner().as_str().parse().map_err(|_| {
-                                Error::MemInfo("Failed to parse SwapTotal".to_string())
-                            })?
+                        swap_total = Some(
+                            pair.into_inner()
+                                .as_str()
+                                .parse()
+                                .map_err(|_| err("Failed to parse SwapTotal"))?,
+                        )
                     }
                     Rule::swapfree => {
-                        swap_free =
-                            pair.into_inner().as_str().parse().map_err(|_| {
-                                Error::MemInfo("Failed to parse SwapFree".to_string())
-                            })?
+                        swap_free = Some(
+                            pair.into_inner()
+                                .as_str()
+                                .parse()
+                                .map_err(|_| err("Failed to parse SwapFree"))?,
+                        )
                     }
                     _ => {}
                 }
+
+                // The order of the lines differs between kernel versions, so only stop
+                // early once every wanted line has been seen
+                if mem_total.is_some()
+                    && mem_free.is_some()
+                    && swap_total.is_some()
+                    && swap_free.is_some()
+                {
+                    break;
+                }
             }
         }
 
         Ok(MemInfo {
-            mem_total: mem_total * 1024,
-            mem_free: mem_free * 1024,
-            swap_total: swap_total * 1024,
-            swap_free: swap_free * 1024,
+            mem_total: mem_total.unwrap_or_default() * 1024,
+            mem_free: mem_free.unwrap_or_default() * 1024,
+            swap_total: swap_total.unwrap_or_default() * 1024,
+            swap_free: swap_free.unwrap_or_default() * 1024,
         })
     }
+}
 
+impl MemInfo {
     pub fn mem_used(&self) -> usize {
         self.mem_total - self.mem_free
     }
@@ -73,3 +102,28 @@ impl MemInfo {
         self.swap_total - self.swap_free
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reordered_lines() -> Result<()> {
+        let mem_info = MemInfo::from_str(
+            "SwapTotal:       2097148 kB\n\
+             SwapFree:        1048576 kB\n\
+             Buffers:           20480 kB\n\
+             Cached:           409600 kB\n\
+             MemTotal:        8048000 kB\n\
+             MemFree:          102400 kB\n\
+             MemAvailable:    4096000 kB\n",
+        )?;
+
+        assert_eq!(mem_info.mem_total, 8048000 * 1024);
+        assert_eq!(mem_info.mem_free, 4096000 * 1024);
+        assert_eq!(mem_info.swap_total, 2097148 * 1024);
+        assert_eq!(mem_info.swap_free, 1048576 * 1024);
+
+        Ok(())
+    }
+}
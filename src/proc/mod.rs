@@ -1,124 +1,294 @@
+pub mod cgroup;
 mod cputime;
+pub mod details;
+mod diff;
+mod faults;
+pub mod flags;
+mod gpu;
+mod io;
 mod loadavg;
 mod meminfo;
 mod prev_cpu;
 pub mod process_info;
+pub mod sched;
+mod smooth;
 mod stat;
 pub mod state;
+mod static_info;
+mod vmstat;
 
-use std::{collections::HashMap, fs, path::PathBuf, time::Duration};
+use std::{
+    collections::HashMap,
+    fs, mem,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use cputime::CpuTime;
-use loadavg::LoadAvg;
-use meminfo::MemInfo;
+use cgroup::Container;
+use cputime::{CpuStat, CpuTime, StatCounters};
+pub use cputime::{CpuUsage, EventRate};
+pub use diff::{ProcessDelta, SystemDiff};
+use faults::{PrevFaults, PrevFaultsMap};
+pub use gpu::GpuInfo;
+use gpu::GpuReader;
+pub use io::SyscallRate;
+use io::{PrevIo, PrevIoMap};
+pub use loadavg::LoadAvg;
+pub use meminfo::MemInfo;
 use prev_cpu::{PrevCpu, PrevCpuMap};
 use process_info::{ProcessInfo, ProcessType};
+use serde::{Deserialize, Serialize};
+use smooth::{Smoothed, SmoothedMap};
 use state::State;
+use static_info::{StaticInfo, StaticInfoMap};
 use thiserror::Error;
+pub use vmstat::SwapRate;
+use vmstat::VmStat;
 
+/// Errors from reading `/proc`. The parsing variants carry a description of the field
+/// that failed and the input that was being parsed.
 #[derive(Debug, Error)]
 pub enum Error {
-    #[error("Failed to read from /proc")]
+    #[error("Failed to read from /proc: {0}")]
     Io(#[from] std::io::Error),
-    #[error("Failed to parse stat")]
-    StatParsing(String),
-    #[error("Failed to read uptime")]
-    Uptime(String),
-    #[error("Failed to read loadavg")]
-    LoadAvg(String),
-    #[error("Failed to read CPU time")]
-    CpuTime(String),
-    #[error("Failed to read meminfo")]
-    MemInfo(String),
+    #[error("Failed to parse stat: {message} in {input:?}")]
+    StatParsing { message: String, input: String },
+    #[error("Failed to read uptime: {message} in {input:?}")]
+    Uptime { message: String, input: String },
+    #[error("Failed to read loadavg: {message} in {input:?}")]
+    LoadAvg { message: String, input: String },
+    #[error("Failed to read CPU time: {message} in {input:?}")]
+    CpuTime { message: String, input: String },
+    #[error("Failed to read meminfo: {message} in {input:?}")]
+    MemInfo { message: String, input: String },
+    #[error("Failed to read vmstat: {message} in {input:?}")]
+    VmStat { message: String, input: String },
+    #[error("Failed to read process io: {message} in {input:?}")]
+    ProcessIo { message: String, input: String },
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Where proc is mounted unless another root is given with [`Proc::root`]
+pub const DEFAULT_ROOT: &str = "/proc";
+
+/// Samples the system from `/proc`.
+///
+/// CPU usage is calculated from the difference between two samples, so the same `Proc`
+/// should be kept around and [`Proc::get_system`] called repeatedly.
 pub struct Proc {
+    root: PathBuf,
     ticks: u64,
     page_size: usize,
     prev_cpus: HashMap<i32, PrevCpu>,
+    prev_faults: HashMap<i32, PrevFaults>,
     prev_cpu_time: Vec<CpuTime>,
+    /// The previous /proc/stat counters and the uptime they were read at
+    prev_counters: Option<(f64, StatCounters)>,
+    /// The previous vmstat and the uptime it was read at
+    prev_vmstat: Option<(f64, VmStat)>,
+    prev_io: HashMap<i32, PrevIo>,
+    /// Weight of the newest sample in the smoothed CPU usage, see [`Proc::smooth_cpu`]
+    cpu_smoothing: Option<f32>,
+    smoothed_cpus: HashMap<i32, Smoothed>,
+    smoothed_usage: Option<CpuUsage>,
+    /// What rarely changes about each process, so that it isn't read every sample
+    static_info: HashMap<i32, StaticInfo>,
+    /// Errors of the sample being read, see [`System::errors`]
+    errors: Vec<Error>,
+    read_fds: bool,
+    read_io: bool,
+    read_gpu_memory: bool,
+    read_swap: bool,
+    read_caps: bool,
+    read_environ: bool,
+    gpus: GpuReader,
 }
 
-#[derive(Default, Debug)]
+/// A single sample of the system.
+#[derive(Default, Debug, Serialize, Deserialize)]
 pub struct System {
     pub processes: Vec<ProcessInfo>,
     pub num_threads: ThreadCount,
+    #[serde(default)]
+    pub num_states: StateCount,
     pub uptime: Duration,
     pub load_avg: LoadAvg,
-    pub cpu_usage: Option<Vec<f32>>,
+    pub cpu_usage: Option<CpuUsage>,
+    /// Interrupts and context switches per second, `None` until a previous sample exists
+    pub event_rate: Option<EventRate>,
     pub mem_usage: MemInfo,
+    /// Pages swapped in and out per second, `None` until a previous sample exists
+    pub swap_rate: Option<SwapRate>,
+    /// Usage of each GPU, `None` when GPU usage can't be read
+    #[serde(default)]
+    pub gpus: Option<Vec<GpuInfo>>,
+    /// The distinct containers the processes run in
+    #[serde(default)]
+    pub containers: Vec<Container>,
+    /// Errors that only lost part of the sample, e.g. a process that couldn't be parsed
+    #[serde(skip)]
+    pub errors: Vec<Error>,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Serialize, Deserialize)]
 pub struct ThreadCount {
     pub tasks: u32,
     pub threads: u32,
     pub kernel_threads: u32,
 }
 
+/// Number of processes in each state
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub struct StateCount {
+    pub running: u32,
+    pub sleeping: u32,
+    /// In uninterruptible sleep, usually waiting for IO
+    pub waiting: u32,
+    pub zombie: u32,
+    /// Stopped by a signal or a debugger
+    pub stopped: u32,
+}
+
+impl Default for Proc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Proc {
     pub fn new() -> Self {
         let ticks = rustix::param::clock_ticks_per_second();
         let page_size = rustix::param::page_size();
         Proc {
+            root: PathBuf::from(DEFAULT_ROOT),
             ticks,
             page_size,
             prev_cpus: HashMap::default(),
+            prev_faults: HashMap::default(),
             prev_cpu_time: Vec::default(),
+            prev_counters: None,
+            prev_vmstat: None,
+            prev_io: HashMap::default(),
+            cpu_smoothing: None,
+            smoothed_cpus: HashMap::default(),
+            smoothed_usage: None,
+            static_info: HashMap::default(),
+            errors: Vec::default(),
+            read_fds: false,
+            read_io: false,
+            read_gpu_memory: false,
+            read_swap: false,
+            read_caps: false,
+            read_environ: false,
+            gpus: GpuReader::new(),
         }
     }
 
+    /// Read from a proc mounted at `root` instead of [`DEFAULT_ROOT`], e.g. the proc of
+    /// a container bind-mounted elsewhere.
+    pub fn root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+
+        self
+    }
+
+    /// Smooth the CPU usage of processes and CPUs with an exponential moving average, with
+    /// `alpha` between 0 and 1 the weight of the newest sample. Lower values are steadier
+    /// but slower to follow changes. The usage of the newest sample alone is kept in
+    /// [`ProcessInfo::raw_cpu_usage`] for sorting.
+    pub fn smooth_cpu(mut self, alpha: Option<f32>) -> Self {
+        self.cpu_smoothing = alpha.map(|alpha| alpha.clamp(f32::EPSILON, 1.0));
+
+        self
+    }
+
+    /// Where proc is read from
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+
+    /// Count the open file descriptors of every process, see [`ProcessInfo::fd_count`]
+    pub fn read_fds(&mut self, read: bool) {
+        self.read_fds = read;
+    }
+
+    /// Read the syscall counts of every process, see [`ProcessInfo::syscall_rate`]
+    pub fn read_io(&mut self, read: bool) {
+        self.read_io = read;
+        self.prev_io.clear();
+    }
+
+    /// Read the GPU memory used by every process, see [`ProcessInfo::gpu_memory`]
+    pub fn read_gpu_memory(&mut self, read: bool) {
+        self.read_gpu_memory = read;
+    }
+
+    /// Read how much memory of every process is swapped out, see [`ProcessInfo::swap`]
+    pub fn read_swap(&mut self, read: bool) {
+        self.read_swap = read;
+    }
+
+    /// Read the effective capabilities of every process, see [`ProcessInfo::cap_eff`]
+    pub fn read_caps(&mut self, read: bool) {
+        self.read_caps = read;
+    }
+
+    /// Read the environment variables of every process, see [`ProcessInfo::environ`]
+    pub fn read_environ(&mut self, read: bool) {
+        self.read_environ = read;
+    }
+
+    /// Forget the previous per-process CPU times, e.g. when switching between reading
+    /// processes and threads.
     pub fn reset_prev_cpus(&mut self) {
         self.prev_cpus.clear();
+        self.prev_faults.clear();
+        self.smoothed_cpus.clear();
+        self.prev_io.clear();
+    }
+
+    /// Forget everything remembered from previous samples, so that the next sample has
+    /// no CPU usage or rates, as if it was the first one.
+    pub fn reset_baselines(&mut self) {
+        self.prev_cpus.clear();
+        self.prev_faults.clear();
+        self.prev_io.clear();
+        self.prev_cpu_time.clear();
+        self.smoothed_cpus.clear();
+        self.smoothed_usage = None;
+        self.prev_counters = None;
+        self.prev_vmstat = None;
     }
 
+    /// Read a new sample, including every thread when `get_threads` is set.
+    ///
+    /// Process and CPU usage is `None` until a previous sample exists to compare against.
     pub fn get_system(&mut self, get_threads: bool) -> Result<System> {
-        let dir_iter = fs::read_dir("/proc")?;
+        // Left over if the previous sample failed
+        self.errors.clear();
+        let dir_iter = fs::read_dir(&self.root)?;
         let mut processes = Vec::new();
         let mut num_threads = ThreadCount::default();
-        let uptime = read_uptime("/proc/uptime".into())?;
+        let mut num_states = StateCount::default();
+        let uptime = read_uptime(self.root.join("uptime"))?;
 
         for entry in dir_iter.flatten() {
             if let Ok(name) = entry.file_name().into_string() {
                 if let Ok(pid) = name.parse::<i32>() {
                     if !get_threads {
-                        if let Some(info) =
-                            ProcessInfo::read(self, pid, pid, &entry.path(), uptime)?
-                        {
-                            if let ProcessType::KernelThread = info.process_type {
-                                num_threads.kernel_threads += 1;
-                            } else {
-                                num_threads.tasks += 1;
-                            }
-
-                            num_threads.threads += info.num_threads - 1;
-
+                        if let Some(info) = self.read_process(pid, pid, &entry.path(), uptime) {
+                            num_threads.count(&info);
+                            num_states.count(&info);
                             processes.push(info);
                         }
                     } else {
-                        let dir_iter = fs::read_dir(entry.path().join("task"))?;
-                        for entry in dir_iter.flatten() {
-                            if let Ok(name) = entry.file_name().into_string() {
-                                if let Ok(tid) = name.parse::<i32>() {
-                                    if let Some(info) =
-                                        ProcessInfo::read(self, tid, pid, &entry.path(), uptime)?
-                                    {
-                                        if tid == pid {
-                                            if let ProcessType::KernelThread = info.process_type {
-                                                num_threads.kernel_threads += 1;
-                                            } else {
-                                                num_threads.tasks += 1;
-                                            }
-
-                                            num_threads.threads += info.num_threads - 1;
-                                        }
-
-                                        processes.push(info);
-                                    }
-                                }
+                        for info in self.read_tasks(pid, &entry.path(), uptime) {
+                            if info.pid == pid {
+                                num_threads.count(&info);
+                                num_states.count(&info);
                             }
+                            processes.push(info);
                         }
                     }
                 }
@@ -126,54 +296,178 @@ impl Proc {
         }
 
         self.prev_cpus.cleanup(uptime);
+        self.prev_faults.cleanup(uptime);
+        self.smoothed_cpus.cleanup(uptime);
+        self.prev_io.cleanup(uptime);
+        self.static_info.cleanup(uptime);
 
-        let load_avg = LoadAvg::load("/proc/loadavg".into())?;
+        if self.read_gpu_memory {
+            // NVML may know of processes that exited since /proc was read, and most
+            // processes don't use the GPU at all
+            let gpu_memory = self.gpus.process_memory();
+            for process in &mut processes {
+                process.gpu_memory = gpu_memory.get(&process.pid).copied();
+            }
+        }
 
-        let input = fs::read_to_string("/proc/stat").unwrap();
-        let cpu_time = cputime::parse_cpu_times(&input)?;
+        let mut containers: Vec<Container> = processes
+            .iter()
+            .filter_map(|process| process.container.clone())
+            .collect();
+        containers.sort();
+        containers.dedup();
 
-        let cpu_usage = if !self.prev_cpu_time.is_empty() {
-            Some(
-                cpu_time
-                    .iter()
-                    .zip(self.prev_cpu_time.iter())
-                    .map(|(new, old)| new.cpu_usage(old))
-                    .collect(),
-            )
-        } else {
-            None
+        let load_avg: LoadAvg = fs::read_to_string(self.root.join("loadavg"))?.parse()?;
+        let cpu_stat: CpuStat = fs::read_to_string(self.root.join("stat"))?.parse()?;
+
+        let cpu_usage = match (
+            cputime::cpu_usage(&cpu_stat.cpu_times, &self.prev_cpu_time),
+            self.cpu_smoothing,
+        ) {
+            (Some(usage), Some(alpha)) => {
+                let smoothed = smooth::smooth_usage(self.smoothed_usage.as_ref(), &usage, alpha);
+                self.smoothed_usage = Some(smoothed.clone());
+                Some(smoothed)
+            }
+            (usage, _) => usage,
         };
+        let counters = cpu_stat.counters;
+        let event_rate = self
+            .prev_counters
+            .map(|(prev_uptime, prev)| counters.rate(&prev, uptime - prev_uptime));
+        self.prev_counters = Some((uptime, counters));
+
+        let mem_usage: MemInfo = fs::read_to_string(self.root.join("meminfo"))?.parse()?;
 
-        let input = fs::read_to_string("/proc/meminfo").unwrap();
-        let mem_usage = MemInfo::parse(&input)?;
+        self.prev_cpu_time = cpu_stat.cpu_times;
 
-        self.prev_cpu_time = cpu_time;
+        // Only the swap rate comes from vmstat, which isn't worth losing the sample over
+        let vmstat = fs::read_to_string(self.root.join("vmstat"))
+            .map_err(Error::from)
+            .and_then(|vmstat| vmstat.parse::<VmStat>());
+        let swap_rate = match vmstat {
+            Ok(vmstat) => {
+                let swap_rate = self
+                    .prev_vmstat
+                    .map(|(prev_uptime, prev)| vmstat.swap_rate(&prev, uptime - prev_uptime));
+                self.prev_vmstat = Some((uptime, vmstat));
+                swap_rate
+            }
+            Err(err) => {
+                log::warn!("No swap rate: {err}");
+                self.errors.push(err);
+                self.prev_vmstat = None;
+                None
+            }
+        };
 
         Ok(System {
             processes,
             num_threads,
+            num_states,
             uptime: Duration::from_secs(uptime as u64),
             load_avg,
             cpu_usage,
+            event_rate,
             mem_usage,
+            swap_rate,
+            gpus: self.gpus.read(),
+            containers,
+            errors: mem::take(&mut self.errors),
         })
     }
+
+    /// Number of online CPUs as of the previous sample
+    fn num_cpus(&self) -> usize {
+        self.prev_cpu_time
+            .iter()
+            .filter(|cpu_time| cpu_time.cpu.is_some())
+            .count()
+    }
+
+    fn read_tasks(&mut self, pid: i32, path: &Path, uptime: f64) -> Vec<ProcessInfo> {
+        // The process may have exited since /proc was listed, which only loses this process
+        let dir_iter = match fs::read_dir(path.join("task")) {
+            Ok(dir_iter) => dir_iter,
+            Err(err) => {
+                log::warn!("Skipping {}: {err}", path.display());
+                return Vec::default();
+            }
+        };
+
+        let mut tasks = Vec::new();
+        for entry in dir_iter.flatten() {
+            if let Ok(name) = entry.file_name().into_string() {
+                if let Ok(tid) = name.parse::<i32>() {
+                    if let Some(info) = self.read_process(tid, pid, &entry.path(), uptime) {
+                        tasks.push(info);
+                    }
+                }
+            }
+        }
+
+        tasks
+    }
+
+    fn read_process(
+        &mut self,
+        pid: i32,
+        parent: i32,
+        path: &Path,
+        uptime: f64,
+    ) -> Option<ProcessInfo> {
+        ProcessInfo::read(self, pid, parent, path, uptime).unwrap_or_else(|err| {
+            log::warn!("Skipping {}: {err}", path.display());
+            self.errors.push(err);
+            None
+        })
+    }
+}
+
+impl ThreadCount {
+    /// Count a process, but not its threads individually. In thread mode only the main
+    /// thread of each process is counted, so the counts are the same in both modes.
+    fn count(&mut self, info: &ProcessInfo) {
+        if let ProcessType::KernelThread = info.process_type {
+            self.kernel_threads += 1;
+        } else {
+            self.tasks += 1;
+        }
+
+        // A process that is exiting can report no threads at all
+        self.threads += info.num_threads.saturating_sub(1);
+    }
+}
+
+impl StateCount {
+    fn count(&mut self, info: &ProcessInfo) {
+        match info.state {
+            State::Running => self.running += 1,
+            State::Sleeping => self.sleeping += 1,
+            State::Waiting => self.waiting += 1,
+            State::Zombie => self.zombie += 1,
+            State::Stopped | State::Tracing => self.stopped += 1,
+            _ => {}
+        }
+    }
 }
 
 fn read_uptime(path: PathBuf) -> Result<f64> {
-    let uptime = fs::read_to_string(&path).map_err(|_| {
-        Error::Uptime(format!(
-            "Could not find an uptime file at {}",
-            path.display()
-        ))
-    })?;
-    let (uptime, _) = uptime
+    parse_uptime(&fs::read_to_string(path)?)
+}
+
+fn parse_uptime(input: &str) -> Result<f64> {
+    let err = |message: &str| Error::Uptime {
+        message: message.to_string(),
+        input: input.to_string(),
+    };
+    let (uptime, _) = input
         .split_once(' ')
-        .ok_or_else(|| Error::Uptime("Failed to split string".to_string()))?;
+        .ok_or_else(|| err("Failed to split string"))?;
 
     uptime
         .parse::<f64>()
-        .map_err(|_| Error::Uptime("Failed to parse uptime to f64".to_string()))
+        .map_err(|_| err("Failed to parse uptime to f64"))
 }
 
 #[cfg(test)]
@@ -193,4 +487,249 @@ mod tests {
 
         Ok(())
     }
+
+    /// Write the system wide files of a proc at `root`
+    fn write_root(root: &Path) -> Result<()> {
+        fs::create_dir_all(root)?;
+        fs::write(root.join("uptime"), "1234.56 4321.00\n")?;
+        fs::write(root.join("loadavg"), "0.50 0.25 0.10 1/100 42\n")?;
+        fs::write(
+            root.join("stat"),
+            "cpu  10 0 10 100 0 0 0 0 0 0\ncpu0 10 0 10 100 0 0 0 0 0 0\n",
+        )?;
+        fs::write(
+            root.join("meminfo"),
+            "MemTotal: 2048 kB\nMemAvailable: 1024 kB\nSwapTotal: 0 kB\nSwapFree: 0 kB\n",
+        )?;
+        fs::write(root.join("vmstat"), "pswpin 0\npswpout 0\n")?;
+
+        Ok(())
+    }
+
+    /// Write the files of process 42 to a proc at `root`
+    fn write_process(root: &Path, name: &str, start_time: u64, cmdline: &str) -> Result<()> {
+        let pid = root.join("42");
+        fs::create_dir_all(&pid)?;
+        fs::write(
+            pid.join("stat"),
+            format!(
+                "42 ({name}) S 1 42 42 0 -1 4194304 0 0 0 0 5 5 0 0 20 0 1 0 {start_time} 4096 2 \
+                 18446744073709551615 0 0 0 0 0 0 0 0 0 0 0 0 17 0 0 0 0 0 0 0 0 0 0 0 0 0 0\n"
+            ),
+        )?;
+        fs::write(pid.join("cmdline"), cmdline)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_from_root() -> Result<()> {
+        let root = std::env::temp_dir().join(format!("fell-test-root-{}", std::process::id()));
+        write_root(&root)?;
+        write_process(&root, "fixture", 100, "fixture\0--flag\0")?;
+
+        let system = Proc::new().root(&root).get_system(false);
+        fs::remove_dir_all(&root)?;
+        let system = system?;
+
+        assert_eq!(system.processes.len(), 1);
+        assert_eq!(system.processes[0].pid, 42);
+        assert_eq!(system.processes[0].name, "fixture");
+        assert_eq!(system.processes[0].cmdline, "fixture --flag");
+        assert_eq!(system.uptime, Duration::from_secs(1234));
+        assert_eq!(system.load_avg.one, 0.5);
+        assert_eq!(system.mem_usage.mem_total, 2048 * 1024);
+
+        Ok(())
+    }
+
+    #[test]
+    fn missing_vmstat_only_loses_the_swap_rate() -> Result<()> {
+        let root = std::env::temp_dir().join(format!("fell-test-vmstat-{}", std::process::id()));
+        write_root(&root)?;
+        write_process(&root, "fixture", 100, "fixture\0")?;
+        fs::remove_file(root.join("vmstat"))?;
+
+        let mut proc = Proc::new().root(&root);
+        proc.get_system(false)?;
+        let system = proc.get_system(false);
+        fs::remove_dir_all(&root)?;
+        let system = system?;
+
+        assert_eq!(system.processes.len(), 1);
+        assert!(system.swap_rate.is_none());
+        assert!(matches!(system.errors[..], [Error::Io(_)]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn status_is_only_read_when_needed() -> Result<()> {
+        let root = std::env::temp_dir().join(format!("fell-test-status-{}", std::process::id()));
+        write_root(&root)?;
+        write_process(&root, "fixture", 100, "fixture\0")?;
+        fs::write(
+            root.join("42/status"),
+            "Name:\tfixture\nVmSwap:\t8 kB\nCapEff:\t0000000000002000\n",
+        )?;
+
+        let mut proc = Proc::new().root(&root);
+        let without = proc.get_system(false);
+        proc.read_caps(true);
+        let caps = proc.get_system(false);
+        proc.read_caps(false);
+        proc.read_swap(true);
+        let swap = proc.get_system(false);
+        fs::remove_dir_all(&root)?;
+
+        let without = &without?.processes[0];
+        assert_eq!((without.cap_eff, without.swap), (None, None));
+        let caps = &caps?.processes[0];
+        assert_eq!((caps.cap_eff, caps.swap), (Some(0x2000), None));
+        let swap = &swap?.processes[0];
+        assert_eq!((swap.cap_eff, swap.swap), (None, Some(8 * 1024)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn environ_is_only_read_when_needed() -> Result<()> {
+        let root = std::env::temp_dir().join(format!("fell-test-environ-{}", std::process::id()));
+        write_root(&root)?;
+        write_process(&root, "fixture", 100, "fixture\0")?;
+        fs::write(root.join("42/environ"), "HOME=/root\0LANG=C\0")?;
+
+        let mut proc = Proc::new().root(&root);
+        let without = proc.get_system(false);
+        proc.read_environ(true);
+        let with = proc.get_system(false);
+        fs::remove_dir_all(&root)?;
+
+        assert_eq!(without?.processes[0].environ, None);
+        assert_eq!(
+            with?.processes[0].environ.as_deref(),
+            Some(&["HOME=/root".to_string(), "LANG=C".to_string()][..])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn reset_baselines_starts_over() -> Result<()> {
+        let mut proc = Proc::new();
+        proc.get_system(false)?;
+        let system = proc.get_system(false)?;
+        assert!(system.cpu_usage.is_some());
+        assert!(system.event_rate.is_some());
+
+        proc.reset_baselines();
+        let system = proc.get_system(false)?;
+        assert!(system.cpu_usage.is_none());
+        assert!(system.event_rate.is_none());
+        assert!(system.swap_rate.is_none());
+        assert!(system.processes.iter().all(|p| p.cpu_usage.is_none()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn missing_task_dir_is_skipped() {
+        let mut proc = Proc::new();
+        let path = std::env::temp_dir().join("fell-test-missing-task-dir");
+
+        assert!(proc.read_tasks(1, &path, 0.0).is_empty());
+    }
+
+    #[test]
+    fn cmdline_is_read_again_for_reused_pid() -> Result<()> {
+        let root = std::env::temp_dir().join(format!("fell-test-reuse-{}", std::process::id()));
+        write_root(&root)?;
+        let mut proc = Proc::new().root(&root);
+        let mut cmdline = |name, start_time, cmdline| -> Result<String> {
+            write_process(&root, name, start_time, cmdline)?;
+            Ok(proc.get_system(false)?.processes.remove(0).cmdline)
+        };
+
+        let first = cmdline("first", 100, "first\0");
+        // Only read once for the same process
+        let cached = cmdline("first", 100, "changed\0");
+        let reused = cmdline("second", 200, "second\0");
+        let exec = cmdline("third", 200, "third\0");
+        fs::remove_dir_all(&root)?;
+
+        assert_eq!(first?, "first");
+        assert_eq!(cached?, "first");
+        assert_eq!(reused?, "second");
+        assert_eq!(exec?, "third");
+
+        Ok(())
+    }
+
+    #[test]
+    fn res_is_rss_pages_times_page_size() -> Result<()> {
+        // Captured together from a `sleep` on a system with 4 KiB pages, with VmRSS at
+        // 1504 kB. Recent kernels show the kernel's approximate RSS counter in stat while
+        // statm and status sum it up exactly, so they can differ by a few dozen pages.
+        let stat = "8682 (sleep) S 8678 8682 8678 0 -1 4194304 114 0 0 0 0 0 0 0 20 0 1 0 \
+                    575616 2560000 336 18446744073709551615 94467949322240 94467949340169 \
+                    140724360254528 0 0 0 0 0 0 1 0 0 17 0 0 0 0 0 0 94467949354256 \
+                    94467949355520 94468776521728 140724360263091 140724360263100 \
+                    140724360263100 140724360265705 0\n";
+        let statm = "625 376 352 5 0 89 0\n";
+
+        let root = std::env::temp_dir().join(format!("fell-test-rss-{}", std::process::id()));
+        write_root(&root)?;
+        fs::create_dir_all(root.join("8682"))?;
+        fs::write(root.join("8682/stat"), stat)?;
+        fs::write(root.join("8682/cmdline"), "sleep\0")?;
+        let mut proc = Proc::new().root(&root);
+        let system = proc.get_system(false);
+        fs::remove_dir_all(&root)?;
+        let process = &system?.processes[0];
+
+        let page_size = rustix::param::page_size();
+        assert_eq!(process.memory, 336 * page_size);
+        // Any other field would be far off from the resident pages in statm
+        let statm_resident: usize = statm.split(' ').nth(1).unwrap().parse().unwrap();
+        assert!(
+            process.memory.abs_diff(statm_resident * page_size) < statm_resident * page_size / 5
+        );
+        // vsize is already in bytes, and matches the size in pages in statm
+        let statm_size: usize = statm.split(' ').next().unwrap().parse().unwrap();
+        assert_eq!(process.virtual_memory, statm_size * 4096);
+
+        Ok(())
+    }
+
+    #[test]
+    fn process_gone_after_stat_is_dropped() -> Result<()> {
+        let root = std::env::temp_dir().join(format!("fell-test-gone-{}", std::process::id()));
+        write_root(&root)?;
+        write_process(&root, "gone", 100, "gone\0")?;
+        // As if the process exited between reading stat and cmdline
+        fs::remove_file(root.join("42/cmdline"))?;
+
+        let system = Proc::new().root(&root).get_system(false);
+        fs::remove_dir_all(&root)?;
+
+        assert!(system?.processes.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn thread_count_of_process_without_threads() {
+        let mut count = ThreadCount::default();
+        count.count(&ProcessInfo {
+            num_threads: 0,
+            ..ProcessInfo::default()
+        });
+        count.count(&ProcessInfo {
+            num_threads: 3,
+            ..ProcessInfo::default()
+        });
+
+        assert_eq!(count.tasks, 2);
+        assert_eq!(count.threads, 2);
+    }
 }
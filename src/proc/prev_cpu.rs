@@ -6,19 +6,41 @@ pub(super) struct PrevCpu {
 }
 
 pub(super) trait PrevCpuMap {
-    fn calculate(&mut self, pid: i32, uptime: f64, cpu_used: u64, ticks: u64) -> Option<f32>;
+    /// Calculate the CPU usage in percent since the previous call for `pid`.
+    ///
+    /// The result is clamped to between 0% and 100% per CPU, so that timing jitter
+    /// between reading uptime and the process times can't produce impossible values.
+    fn calculate(
+        &mut self,
+        pid: i32,
+        uptime: f64,
+        cpu_used: u64,
+        ticks: u64,
+        num_cpus: usize,
+    ) -> Option<f32>;
     fn cleanup(&mut self, uptime: f64);
 }
 
 impl PrevCpuMap for HashMap<i32, PrevCpu> {
-    fn calculate(&mut self, pid: i32, uptime: f64, cpu_used: u64, ticks: u64) -> Option<f32> {
+    fn calculate(
+        &mut self,
+        pid: i32,
+        uptime: f64,
+        cpu_used: u64,
+        ticks: u64,
+        num_cpus: usize,
+    ) -> Option<f32> {
         if let Some(prev_cpu) = self.get_mut(&pid) {
-            let cpu_usage = (cpu_used - prev_cpu.cpu_used) as f64 * 100.0
-                / ((uptime - prev_cpu.uptime) * ticks as f64);
+            let elapsed = uptime - prev_cpu.uptime;
+            let cpu_usage = if elapsed > 0.0 {
+                cpu_used.saturating_sub(prev_cpu.cpu_used) as f64 * 100.0 / (elapsed * ticks as f64)
+            } else {
+                0.0
+            };
             prev_cpu.uptime = uptime;
             prev_cpu.cpu_used = cpu_used;
 
-            Some(cpu_usage as f32)
+            Some((cpu_usage as f32).clamp(0.0, 100.0 * num_cpus.max(1) as f32))
         } else {
             self.insert(pid, PrevCpu { uptime, cpu_used });
 
@@ -30,3 +52,26 @@ impl PrevCpuMap for HashMap<i32, PrevCpu> {
         self.retain(|_, p| p.uptime.eq(&uptime));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn usage_is_clamped_to_cpu_count() {
+        let mut prev_cpus = HashMap::default();
+
+        assert_eq!(prev_cpus.calculate(1, 10.0, 0, 100, 2), None);
+        // 1000 ticks in one second at 100 ticks per second would be 1000%
+        assert_eq!(prev_cpus.calculate(1, 11.0, 1_000, 100, 2), Some(200.0));
+    }
+
+    #[test]
+    fn usage_is_never_negative() {
+        let mut prev_cpus = HashMap::default();
+
+        prev_cpus.calculate(1, 10.0, 100, 100, 1);
+        assert_eq!(prev_cpus.calculate(1, 11.0, 99, 100, 1), Some(0.0));
+        assert_eq!(prev_cpus.calculate(1, 10.9, 150, 100, 1), Some(0.0));
+    }
+}
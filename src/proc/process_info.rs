@@ -1,22 +1,113 @@
-use std::{fs, path::Path};
+use std::{fs, io, path::Path};
 
-use super::{prev_cpu::PrevCpuMap, stat::Stat, state::State, Proc, Result};
+use rustix::io::Errno;
+use serde::{Deserialize, Serialize};
 
-#[derive(Default, Debug)]
+use super::{
+    cgroup::{parse_cgroup, Container},
+    details::read_environ,
+    faults::PrevFaultsMap,
+    flags::ProcessFlags,
+    io::PrevIoMap,
+    prev_cpu::PrevCpuMap,
+    sched::SchedPolicy,
+    smooth::SmoothedMap,
+    stat::Stat,
+    state::State,
+    static_info::{StaticInfo, StaticInfoMap},
+    Proc, Result, SyscallRate,
+};
+
+#[derive(Default, Debug, Serialize, Deserialize)]
 pub struct ProcessInfo {
     pub pid: i32,
+    /// Id of the thread group, i.e. the process a thread belongs to. Equal to `pid` for
+    /// processes and kernel threads.
+    pub tgid: i32,
+    /// Id of the parent process, 0 for the processes started by the kernel
+    #[serde(default)]
+    pub ppid: i32,
+    /// Id of the process group, e.g. the processes of a shell pipeline
+    #[serde(default)]
+    pub pgrp: i32,
+    /// Id of the session, e.g. the processes started from a terminal
+    #[serde(default)]
+    pub session: i32,
     pub uid: Option<u32>,
     pub name: String,
     pub state: State,
     pub memory: usize,
     pub virtual_memory: usize,
+    /// CPU usage in percent since the previous sample, smoothed over the previous samples
+    /// when enabled with [`Proc::smooth_cpu`]
+    ///
+    /// [`Proc::smooth_cpu`]: super::Proc::smooth_cpu
     pub cpu_usage: Option<f32>,
+    /// CPU usage of the newest sample alone when `cpu_usage` is smoothed, otherwise `None`
+    #[serde(default)]
+    pub raw_cpu_usage: Option<f32>,
+    /// The command line with the arguments separated by spaces
     pub cmdline: String,
+    /// The separate arguments of the command line
+    pub args: Vec<String>,
     pub process_type: ProcessType,
     pub num_threads: u32,
+    /// Scheduling priority as shown by the kernel
+    pub priority: i32,
+    /// Nice value from -20 (highest priority) to 19 (lowest priority)
+    pub nice: i32,
+    /// Scheduling policy, `None` if the kernel doesn't show it
+    #[serde(default)]
+    pub sched_policy: Option<SchedPolicy>,
+    /// Priority from 1 to 99 under a realtime policy, 0 otherwise
+    #[serde(default)]
+    pub rt_priority: u32,
+    /// Time the process started after boot, in clock ticks
+    pub start_time: u64,
+    /// Number of open file descriptors, only read when enabled with [`Proc::read_fds`]
+    ///
+    /// [`Proc::read_fds`]: super::Proc::read_fds
+    pub fd_count: Option<usize>,
+    /// Read and write syscalls per second, only read when enabled with [`Proc::read_io`]
+    /// and once there is a previous sample to compare with
+    ///
+    /// [`Proc::read_io`]: super::Proc::read_io
+    pub syscall_rate: Option<SyscallRate>,
+    /// Major page faults per second, i.e. pages that had to be read from disk or swap,
+    /// once there is a previous sample to compare with
+    #[serde(default)]
+    pub major_fault_rate: Option<f32>,
+    /// GPU memory in bytes, only read when enabled with [`Proc::read_gpu_memory`] and
+    /// `None` for processes not using a GPU
+    ///
+    /// [`Proc::read_gpu_memory`]: super::Proc::read_gpu_memory
+    pub gpu_memory: Option<u64>,
+    /// Memory swapped out in bytes, only read when enabled with [`Proc::read_swap`].
+    /// Kernel threads have no memory of their own and never have a value.
+    ///
+    /// [`Proc::read_swap`]: super::Proc::read_swap
+    #[serde(default)]
+    pub swap: Option<usize>,
+    /// The kernel's flags for the process, if they could be parsed
+    pub flags: Option<ProcessFlags>,
+    /// The effective capability set, only read when enabled with [`Proc::read_caps`] and
+    /// `None` if `/proc/[pid]/status` couldn't be read
+    ///
+    /// [`Proc::read_caps`]: super::Proc::read_caps
+    pub cap_eff: Option<u64>,
+    /// The `KEY=value` environment variables the process was started with, only read
+    /// when enabled with [`Proc::read_environ`]. Empty for other users' processes, whose
+    /// environment can't be read.
+    ///
+    /// [`Proc::read_environ`]: super::Proc::read_environ
+    #[serde(default)]
+    pub environ: Option<Vec<String>>,
+    /// The container the process runs in, if any
+    #[serde(default)]
+    pub container: Option<Container>,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ProcessType {
     #[default]
     Task,
@@ -24,7 +115,77 @@ pub enum ProcessType {
     KernelThread,
 }
 
+impl ProcessType {
+    fn classify(flags: Option<ProcessFlags>, cmdline: &str, pid: i32, parent: i32) -> Self {
+        // Zombies and exiting processes also have an empty cmdline, so only fall back
+        // to checking it when the flags are unknown
+        let kernel_thread = match flags {
+            Some(flags) => flags.is_kernel_thread(),
+            None => cmdline.is_empty(),
+        };
+
+        if kernel_thread {
+            ProcessType::KernelThread
+        } else if pid == parent {
+            ProcessType::Task
+        } else {
+            ProcessType::Thread
+        }
+    }
+}
+
 impl ProcessInfo {
+    /// The CPU usage to sort by, which isn't smoothed so that the order follows the
+    /// newest sample
+    pub fn sort_cpu_usage(&self) -> Option<f32> {
+        self.raw_cpu_usage.or(self.cpu_usage)
+    }
+
+    /// Whether the process runs as root or has any effective capabilities
+    pub fn is_privileged(&self) -> bool {
+        self.uid == Some(0) || self.cap_eff.is_some_and(|caps| caps != 0)
+    }
+
+    /// The name of the executable from the command line, falling back to the kernel's name
+    /// for the process, which is cut at 15 characters, when there is no command line.
+    pub fn display_name(&self) -> &str {
+        self.args
+            .first()
+            .and_then(|argv0| argv0.rsplit('/').next())
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&self.name)
+    }
+
+    /// The first argument of the command line, usually the path the executable was run
+    /// as, falling back to the kernel's name for the process when there is no command line
+    pub fn argv0(&self) -> &str {
+        self.args
+            .first()
+            .filter(|argv0| !argv0.is_empty())
+            .map_or(&self.name, |argv0| argv0)
+    }
+
+    /// The command line with arguments containing whitespace quoted, so the boundaries
+    /// between them are visible
+    pub fn quoted_cmdline(&self) -> String {
+        // Processes that rewrite their command line often put it all in one argument
+        if self.args.len() < 2 {
+            return self.cmdline.clone();
+        }
+
+        self.args
+            .iter()
+            .map(|arg| {
+                if arg.is_empty() || arg.contains(char::is_whitespace) || arg.contains('\'') {
+                    format!("'{}'", arg.replace('\'', "'\\''"))
+                } else {
+                    arg.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
     pub(super) fn read(
         proc: &mut Proc,
         pid: i32,
@@ -33,43 +194,278 @@ impl ProcessInfo {
         uptime: f64,
     ) -> Result<Option<Self>> {
         if let Ok(stat) = fs::read_to_string(path.join("stat")) {
-            let uid = if let Ok(stat) = rustix::fs::stat(path) {
-                Some(stat.st_uid)
+            let stat: Stat = stat.parse()?;
+            let info = match proc
+                .static_info
+                .lookup(pid, uptime, stat.start_time, &stat.name)
+            {
+                Some(info) => info.clone(),
+                None => {
+                    let Some(info) = read_static_info(path, uptime, &stat) else {
+                        return Ok(None);
+                    };
+                    proc.static_info.insert(pid, info.clone());
+                    info
+                }
+            };
+
+            // Listing fd is relatively expensive and fails for other users' processes
+            let fd_count = if proc.read_fds {
+                fs::read_dir(path.join("fd"))
+                    .map(|entries| entries.count())
+                    .ok()
             } else {
                 None
             };
-            let cmdline = fs::read_to_string(path.join("cmdline"))
-                .unwrap_or_default()
-                .replace('\0', " ")
-                .trim()
-                .to_string();
-
-            let stat = Stat::parse(&stat)?;
-
-            let process_type = if cmdline.is_empty() {
-                ProcessType::KernelThread
-            } else if pid == parent {
-                ProcessType::Task
+
+            // Only read status while something needs it, it is one more file per task
+            let status = if proc.read_caps || proc.read_swap {
+                fs::read_to_string(path.join("status"))
+                    .inspect_err(|err| {
+                        log::debug!("Failed to read {}/status: {err}", path.display())
+                    })
+                    .ok()
             } else {
-                ProcessType::Thread
+                None
+            };
+            let cap_eff = status
+                .as_deref()
+                .filter(|_| proc.read_caps)
+                .and_then(parse_cap_eff);
+            let swap = status
+                .as_deref()
+                .filter(|_| proc.read_swap)
+                .and_then(parse_vm_swap);
+            let environ = proc
+                .read_environ
+                .then(|| read_environ(path).unwrap_or_default());
+            // Other users' io can't be read without privileges
+            let syscall_rate = if proc.read_io {
+                fs::read_to_string(path.join("io"))
+                    .ok()
+                    .and_then(|io| io.parse().ok())
+                    .and_then(|counters| proc.prev_io.calculate(pid, uptime, counters))
+            } else {
+                None
+            };
+
+            let process_type = ProcessType::classify(stat.flags, &info.cmdline, pid, parent);
+            let raw_cpu_usage =
+                proc.prev_cpus
+                    .calculate(pid, uptime, stat.cpu_used, proc.ticks, proc.num_cpus());
+            let cpu_usage = match (raw_cpu_usage, proc.cpu_smoothing) {
+                (Some(sample), Some(alpha)) => {
+                    Some(
+                        proc.smoothed_cpus
+                            .smooth(pid, uptime, stat.start_time, sample, alpha),
+                    )
+                }
+                (usage, _) => usage,
             };
 
             Ok(Some(ProcessInfo {
                 pid,
-                uid,
+                tgid: parent,
+                ppid: stat.ppid,
+                pgrp: stat.pgrp,
+                session: stat.session,
+                uid: info.uid,
                 name: stat.name,
                 state: stat.state,
                 memory: stat.memory_res * proc.page_size,
                 virtual_memory: stat.memory_virtual,
-                cpu_usage: proc
-                    .prev_cpus
-                    .calculate(pid, uptime, stat.cpu_used, proc.ticks),
-                cmdline,
+                cpu_usage,
+                raw_cpu_usage: raw_cpu_usage.filter(|_| proc.cpu_smoothing.is_some()),
+                cmdline: info.cmdline,
+                args: info.args,
                 process_type,
                 num_threads: stat.num_threads,
+                priority: stat.priority,
+                nice: stat.nice,
+                sched_policy: stat.policy,
+                rt_priority: stat.rt_priority,
+                start_time: stat.start_time,
+                fd_count,
+                syscall_rate,
+                major_fault_rate: proc.prev_faults.calculate(pid, uptime, stat.major_faults),
+                gpu_memory: None,
+                swap,
+                flags: stat.flags,
+                cap_eff,
+                environ,
+                container: info.container,
             }))
         } else {
             Ok(None)
         }
     }
 }
+
+/// Read what is cached in [`StaticInfo`] for the process at `path`
+///
+/// `None` if the process exited after its `stat` was read, so that it isn't shown with
+/// half of its fields missing.
+fn read_static_info(path: &Path, uptime: f64, stat: &Stat) -> Option<StaticInfo> {
+    let uid = match rustix::fs::stat(path) {
+        Ok(stat) => Some(stat.st_uid),
+        Err(Errno::NOENT | Errno::SRCH) => return None,
+        Err(err) => {
+            log::debug!("Failed to stat {}: {err}", path.display());
+            None
+        }
+    };
+    let args = parse_cmdline(&match fs::read_to_string(path.join("cmdline")) {
+        Ok(cmdline) => cmdline,
+        Err(err) if exited(&err) => return None,
+        Err(err) => {
+            log::debug!("Failed to read {}/cmdline: {err}", path.display());
+            String::default()
+        }
+    });
+    let container = fs::read_to_string(path.join("cgroup"))
+        .ok()
+        .and_then(|cgroup| parse_cgroup(&cgroup));
+
+    Some(StaticInfo {
+        uptime,
+        start_time: stat.start_time,
+        name: stat.name.clone(),
+        uid,
+        cmdline: args.join(" ").trim().to_string(),
+        args,
+        container,
+    })
+}
+
+/// Whether reading a file of a process failed because the process is gone
+fn exited(err: &io::Error) -> bool {
+    err.kind() == io::ErrorKind::NotFound || err.raw_os_error() == Some(Errno::SRCH.raw_os_error())
+}
+
+/// The hexadecimal `CapEff` mask from a `status` file
+fn parse_cap_eff(status: &str) -> Option<u64> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("CapEff:"))
+        .and_then(|caps| u64::from_str_radix(caps.trim(), 16).ok())
+}
+
+/// The `VmSwap` size from a `status` file in bytes, missing for kernel threads
+fn parse_vm_swap(status: &str) -> Option<usize> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmSwap:"))
+        .and_then(|swap| swap.trim().strip_suffix(" kB"))
+        .and_then(|kib| kib.trim().parse::<usize>().ok())
+        .map(|kib| kib * 1024)
+}
+
+/// Split the `\0` separated arguments of a `cmdline` file
+fn parse_cmdline(cmdline: &str) -> Vec<String> {
+    cmdline
+        .strip_suffix('\0')
+        .unwrap_or(cmdline)
+        .split('\0')
+        .filter(|_| !cmdline.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn zombie_is_not_a_kernel_thread() -> Result<()> {
+        let stat = Stat::from_str(
+            "4242 (defunct) Z 1 4242 4242 0 -1 4227596 0 0 0 0 0 0 0 0 20 0 1 0 1234 0 0 \
+             18446744073709551615 0 0 0 0 0 0 0 0 0 0 0 0 17 0 0 0 0 0 0 0 0 0 0 0 0 0 0",
+        )?;
+        assert_eq!(
+            ProcessType::classify(stat.flags, "", 4242, 4242),
+            ProcessType::Task
+        );
+
+        let stat = Stat::from_str(
+            "2 (kthreadd) S 0 0 0 0 -1 2129984 0 0 0 0 0 0 0 0 20 0 1 0 10 0 0 \
+             18446744073709551615 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0",
+        )?;
+        assert_eq!(
+            ProcessType::classify(stat.flags, "", 2, 2),
+            ProcessType::KernelThread
+        );
+
+        assert_eq!(
+            ProcessType::classify(None, "", 10, 10),
+            ProcessType::KernelThread
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn display_name_from_cmdline() {
+        let process = ProcessInfo {
+            name: "systemd-journal".to_string(),
+            args: parse_cmdline("/usr/lib/systemd/systemd-journald\0--flag\0"),
+            ..Default::default()
+        };
+        assert_eq!(process.display_name(), "systemd-journald");
+        assert_eq!(process.argv0(), "/usr/lib/systemd/systemd-journald");
+
+        let kernel_thread = ProcessInfo {
+            name: "kworker/0:1".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(kernel_thread.display_name(), "kworker/0:1");
+        assert_eq!(kernel_thread.argv0(), "kworker/0:1");
+    }
+
+    #[test]
+    fn args_with_spaces_are_quoted() {
+        let args = parse_cmdline("sh\0-c\0echo 'hi' there\0\0");
+        assert_eq!(args, vec!["sh", "-c", "echo 'hi' there", ""]);
+
+        let process = ProcessInfo {
+            cmdline: args.join(" "),
+            args,
+            ..Default::default()
+        };
+        assert_eq!(
+            process.quoted_cmdline(),
+            "sh -c 'echo '\\''hi'\\'' there' ''"
+        );
+
+        assert!(parse_cmdline("").is_empty());
+    }
+
+    #[test]
+    fn effective_capabilities() {
+        let status = "Name:\tping\nUid:\t1000\t1000\t1000\t1000\n\
+                      CapInh:\t0000000000000000\nCapEff:\t0000000000002000\n";
+        assert_eq!(parse_cap_eff(status), Some(0x2000));
+        assert_eq!(parse_cap_eff("Name:\tbash\n"), None);
+
+        let process = ProcessInfo {
+            uid: Some(1000),
+            cap_eff: parse_cap_eff(status),
+            ..ProcessInfo::default()
+        };
+        assert!(process.is_privileged());
+        assert!(!ProcessInfo {
+            uid: Some(1000),
+            cap_eff: Some(0),
+            ..ProcessInfo::default()
+        }
+        .is_privileged());
+    }
+
+    #[test]
+    fn swapped_out_memory() {
+        let status = "Name:\tfirefox\nVmRSS:\t  204800 kB\nVmSwap:\t    1536 kB\n";
+        assert_eq!(parse_vm_swap(status), Some(1536 * 1024));
+        assert_eq!(parse_vm_swap("Name:\tkthreadd\n"), None);
+    }
+}
@@ -0,0 +1,56 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// Scheduling policy from field 41 of `/proc/[pid]/stat`, one of the kernel's `SCHED_*`
+/// constants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SchedPolicy {
+    /// `SCHED_OTHER`, the default time-sharing policy
+    Other,
+    Fifo,
+    RoundRobin,
+    Batch,
+    Idle,
+    Deadline,
+    Unknown(u32),
+}
+
+impl From<u32> for SchedPolicy {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => SchedPolicy::Other,
+            1 => SchedPolicy::Fifo,
+            2 => SchedPolicy::RoundRobin,
+            3 => SchedPolicy::Batch,
+            5 => SchedPolicy::Idle,
+            6 => SchedPolicy::Deadline,
+            value => SchedPolicy::Unknown(value),
+        }
+    }
+}
+
+impl SchedPolicy {
+    /// Whether the policy is one of the realtime ones, which run before every process
+    /// with a normal policy
+    pub fn is_realtime(&self) -> bool {
+        matches!(
+            self,
+            SchedPolicy::Fifo | SchedPolicy::RoundRobin | SchedPolicy::Deadline
+        )
+    }
+}
+
+impl Display for SchedPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchedPolicy::Other => write!(f, "OTHER"),
+            SchedPolicy::Fifo => write!(f, "FIFO"),
+            SchedPolicy::RoundRobin => write!(f, "RR"),
+            SchedPolicy::Batch => write!(f, "BATCH"),
+            SchedPolicy::Idle => write!(f, "IDLE"),
+            SchedPolicy::Deadline => write!(f, "DL"),
+            SchedPolicy::Unknown(value) => write!(f, "?{value}"),
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use super::CpuUsage;
+
+/// Exponential moving average of `sample` and the previous average, with `alpha` the
+/// weight of the new sample. The first sample is taken as is, rather than averaged
+/// with 0, so that the average doesn't start out low.
+pub(super) fn ema(prev: Option<f32>, sample: f32, alpha: f32) -> f32 {
+    match prev {
+        Some(prev) => alpha * sample + (1.0 - alpha) * prev,
+        None => sample,
+    }
+}
+
+pub(super) struct Smoothed {
+    /// Uptime of the sample the process was last seen in
+    pub(super) uptime: f64,
+    /// Together with the pid identifies the process, since pids are reused
+    pub(super) start_time: u64,
+    pub(super) value: f32,
+}
+
+pub(super) trait SmoothedMap {
+    /// Average `sample` with the previous ones of `pid`, starting over if the pid now
+    /// belongs to another process
+    fn smooth(&mut self, pid: i32, uptime: f64, start_time: u64, sample: f32, alpha: f32) -> f32;
+    fn cleanup(&mut self, uptime: f64);
+}
+
+impl SmoothedMap for HashMap<i32, Smoothed> {
+    fn smooth(&mut self, pid: i32, uptime: f64, start_time: u64, sample: f32, alpha: f32) -> f32 {
+        let prev = self
+            .get(&pid)
+            .filter(|prev| prev.start_time == start_time)
+            .map(|prev| prev.value);
+        let value = ema(prev, sample, alpha);
+        self.insert(
+            pid,
+            Smoothed {
+                uptime,
+                start_time,
+                value,
+            },
+        );
+
+        value
+    }
+
+    fn cleanup(&mut self, uptime: f64) {
+        self.retain(|_, s| s.uptime.eq(&uptime));
+    }
+}
+
+/// Average `usage` with `prev`, the previous average, starting over when the set of
+/// online CPUs changed
+pub(super) fn smooth_usage(prev: Option<&CpuUsage>, usage: &CpuUsage, alpha: f32) -> CpuUsage {
+    let same_cores = |prev: &&CpuUsage| {
+        prev.cores
+            .iter()
+            .map(|(cpu, _)| cpu)
+            .eq(usage.cores.iter().map(|(cpu, _)| cpu))
+    };
+    let Some(prev) = prev.filter(same_cores) else {
+        return usage.clone();
+    };
+
+    CpuUsage {
+        total: ema(Some(prev.total), usage.total, alpha),
+        cores: prev
+            .cores
+            .iter()
+            .zip(&usage.cores)
+            .map(|((cpu, prev), (_, sample))| (*cpu, ema(Some(*prev), *sample, alpha)))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ema_is_seeded_with_the_first_sample() {
+        assert_eq!(ema(None, 40.0, 0.5), 40.0);
+        assert_eq!(ema(Some(40.0), 80.0, 0.5), 60.0);
+        assert_eq!(ema(Some(60.0), 80.0, 0.25), 65.0);
+        // Without any weight on the past it is the raw sample
+        assert_eq!(ema(Some(60.0), 0.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn reused_pid_starts_over() {
+        let mut smoothed = HashMap::default();
+
+        assert_eq!(smoothed.smooth(1, 10.0, 100, 50.0, 0.5), 50.0);
+        assert_eq!(smoothed.smooth(1, 11.0, 100, 100.0, 0.5), 75.0);
+        assert_eq!(smoothed.smooth(1, 12.0, 200, 10.0, 0.5), 10.0);
+
+        smoothed.cleanup(13.0);
+        assert!(smoothed.is_empty());
+    }
+
+    #[test]
+    fn changed_cores_start_over() {
+        let usage = |cores: &[(u32, f32)]| CpuUsage {
+            total: 20.0,
+            cores: cores.to_vec(),
+        };
+        let prev = usage(&[(0, 10.0), (1, 30.0)]);
+
+        let smoothed = smooth_usage(Some(&prev), &usage(&[(0, 30.0), (1, 50.0)]), 0.5);
+        assert_eq!(smoothed.cores, [(0, 20.0), (1, 40.0)]);
+
+        // CPU 1 went offline and CPU 2 came online
+        let smoothed = smooth_usage(Some(&prev), &usage(&[(0, 30.0), (2, 50.0)]), 0.5);
+        assert_eq!(smoothed.cores, [(0, 30.0), (2, 50.0)]);
+    }
+}
@@ -1,7 +1,9 @@
+use std::str::FromStr;
+
 use pest::Parser;
 use pest_derive::Parser;
 
-use super::{Error, Result, State};
+use super::{flags::ProcessFlags, sched::SchedPolicy, Error, Result, State};
 
 #[derive(Parser)]
 #[grammar = "proc/stat.pest"]
@@ -10,135 +12,240 @@ struct StatParser;
 #[derive(Default, Debug)]
 pub(super) struct Stat {
     pub(super) name: String,
+    pub(super) ppid: i32,
+    pub(super) pgrp: i32,
+    pub(super) session: i32,
+    /// Resident set size in pages, field 24 `rss`
     pub(super) memory_res: usize,
+    /// Virtual memory size in bytes, field 23 `vsize`
     pub(super) memory_virtual: usize,
     pub(super) state: State,
     pub(super) cpu_used: u64,
+    /// Page faults that had to read from disk, field 12 `majflt`
+    pub(super) major_faults: u64,
     pub(super) num_threads: u32,
+    pub(super) priority: i32,
+    pub(super) nice: i32,
+    pub(super) start_time: u64,
+    /// The kernel's `PF_*` flags, or `None` if they couldn't be parsed
+    pub(super) flags: Option<ProcessFlags>,
+    pub(super) rt_priority: u32,
+    pub(super) policy: Option<SchedPolicy>,
 }
 
-impl Stat {
-    pub(super) fn parse(value: &str) -> Result<Self> {
+impl FromStr for Stat {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let err = |message: &str| Error::StatParsing {
+            message: message.to_string(),
+            input: value.to_string(),
+        };
         let mut record = StatParser::parse(Rule::record, value)
-            .map_err(|_| Error::StatParsing("Failed to parse values from string".to_string()))?
+            .map_err(|_| err("Failed to parse values from string"))?
             .next()
             .unwrap()
             .into_inner();
 
-        record
-            .next()
-            .ok_or(Error::StatParsing("Failed to skip pid".to_string()))?;
+        record.next().ok_or_else(|| err("Failed to skip pid"))?;
 
         let name = record
             .next()
-            .ok_or(Error::StatParsing("Failed to read comm".to_string()))?
+            .ok_or_else(|| err("Failed to read comm"))?
             .into_inner()
             .as_str()
             .to_string();
 
         let state: State = record
             .next()
-            .ok_or(Error::StatParsing("Failed to read state".to_string()))?
+            .ok_or_else(|| err("Failed to read state"))?
             .into_inner()
             .as_str()
             .into();
 
-        record
-            .next()
-            .ok_or(Error::StatParsing("Failed to skip ppid".to_string()))?;
-        record
-            .next()
-            .ok_or(Error::StatParsing("Failed to skip pgrp".to_string()))?;
-        record
-            .next()
-            .ok_or(Error::StatParsing("Failed to skip session".to_string()))?;
-        record
-            .next()
-            .ok_or(Error::StatParsing("Failed to skip tty_nr".to_string()))?;
-        record
-            .next()
-            .ok_or(Error::StatParsing("Failed to skip tpgid".to_string()))?;
-        record
+        let ppid: i32 = record
             .next()
-            .ok_or(Error::StatParsing("Failed to skip flags".to_string()))?;
-        record
+            .ok_or_else(|| err("Failed to read ppid"))?
+            .into_inner()
+            .as_str()
+            .parse()
+            .map_err(|_| err("Failed to parse ppid to i32"))?;
+        let pgrp: i32 = record
             .next()
-            .ok_or(Error::StatParsing("Failed to skip minflt".to_string()))?;
-        record
+            .ok_or_else(|| err("Failed to read pgrp"))?
+            .into_inner()
+            .as_str()
+            .parse()
+            .map_err(|_| err("Failed to parse pgrp to i32"))?;
+        let session: i32 = record
             .next()
-            .ok_or(Error::StatParsing("Failed to skip cminflt".to_string()))?;
-        record
+            .ok_or_else(|| err("Failed to read session"))?
+            .into_inner()
+            .as_str()
+            .parse()
+            .map_err(|_| err("Failed to parse session to i32"))?;
+        record.next().ok_or_else(|| err("Failed to skip tty_nr"))?;
+        record.next().ok_or_else(|| err("Failed to skip tpgid"))?;
+        let flags = record
             .next()
-            .ok_or(Error::StatParsing("Failed to skip majflt".to_string()))?;
-        record
+            .ok_or_else(|| err("Failed to read flags"))?
+            .into_inner()
+            .as_str()
+            .parse()
+            .ok()
+            .map(ProcessFlags);
+        record.next().ok_or_else(|| err("Failed to skip minflt"))?;
+        record.next().ok_or_else(|| err("Failed to skip cminflt"))?;
+        let major_faults: u64 = record
             .next()
-            .ok_or(Error::StatParsing("Failed to skip cmajflt".to_string()))?;
+            .ok_or_else(|| err("Failed to read majflt"))?
+            .into_inner()
+            .as_str()
+            .parse()
+            .map_err(|_| err("Failed to parse majflt to u64"))?;
+        record.next().ok_or_else(|| err("Failed to skip cmajflt"))?;
 
         let utime: u64 = record
             .next()
-            .ok_or(Error::StatParsing("Failed to read utime".to_string()))?
+            .ok_or_else(|| err("Failed to read utime"))?
             .into_inner()
             .as_str()
             .parse()
-            .map_err(|_| Error::StatParsing("Failed to parse utime to u64".to_string()))?;
+            .map_err(|_| err("Failed to parse utime to u64"))?;
         let stime: u64 = record
             .next()
-            .ok_or(Error::StatParsing("Failed to read stime".to_string()))?
+            .ok_or_else(|| err("Failed to read stime"))?
             .into_inner()
             .as_str()
             .parse()
-            .map_err(|_| Error::StatParsing("Failed to parse stime to u64".to_string()))?;
+            .map_err(|_| err("Failed to parse stime to u64"))?;
 
-        record
-            .next()
-            .ok_or(Error::StatParsing("Failed to skip cutime".to_string()))?;
-        record
-            .next()
-            .ok_or(Error::StatParsing("Failed to skip cstime".to_string()))?;
-        record
+        record.next().ok_or_else(|| err("Failed to skip cutime"))?;
+        record.next().ok_or_else(|| err("Failed to skip cstime"))?;
+        let priority: i32 = record
             .next()
-            .ok_or(Error::StatParsing("Failed to skip priority".to_string()))?;
-        record
+            .ok_or_else(|| err("Failed to read priority"))?
+            .into_inner()
+            .as_str()
+            .parse()
+            .map_err(|_| err("Failed to parse priority to i32"))?;
+        let nice: i32 = record
             .next()
-            .ok_or(Error::StatParsing("Failed to skip nice".to_string()))?;
+            .ok_or_else(|| err("Failed to read nice"))?
+            .into_inner()
+            .as_str()
+            .parse()
+            .map_err(|_| err("Failed to parse nice to i32"))?;
 
         let num_threads: u32 = record
             .next()
-            .ok_or(Error::StatParsing("Failed to read num_threads".to_string()))?
+            .ok_or_else(|| err("Failed to read num_threads"))?
             .into_inner()
             .as_str()
             .parse()
-            .map_err(|_| Error::StatParsing("Failed to parse num_threads to u32".to_string()))?;
+            .map_err(|_| err("Failed to parse num_threads to u32"))?;
 
         record
             .next()
-            .ok_or(Error::StatParsing("Failed to skip itrealvalue".to_string()))?;
-        record
+            .ok_or_else(|| err("Failed to skip itrealvalue"))?;
+        let start_time: u64 = record
             .next()
-            .ok_or(Error::StatParsing("Failed to skip starttime".to_string()))?;
+            .ok_or_else(|| err("Failed to read starttime"))?
+            .into_inner()
+            .as_str()
+            .parse()
+            .map_err(|_| err("Failed to parse starttime to u64"))?;
 
         let memory_virtual: usize = record
             .next()
-            .ok_or(Error::StatParsing("Failed to read vsize".to_string()))?
+            .ok_or_else(|| err("Failed to read vsize"))?
             .into_inner()
             .as_str()
             .parse()
-            .map_err(|_| Error::StatParsing("Failed to parse vsize to usize".to_string()))?;
+            .map_err(|_| err("Failed to parse vsize to usize"))?;
         let memory_res: usize = record
             .next()
-            .ok_or(Error::StatParsing("Failed to read rss".to_string()))?
+            .ok_or_else(|| err("Failed to read rss"))?
             .into_inner()
             .as_str()
             .parse()
-            .map_err(|_| Error::StatParsing("Failed to parse rss to usize".to_string()))?;
+            .map_err(|_| err("Failed to parse rss to usize"))?;
+
+        // Fields 25 to 39 aren't used. Kernels before 2.5.19 end before rt_priority and
+        // policy, so those aren't required.
+        let mut number = |skip| {
+            record
+                .nth(skip)
+                .and_then(|value| value.into_inner().as_str().parse::<u32>().ok())
+        };
+        let rt_priority = number(15).unwrap_or_default();
+        let policy = number(0).map(SchedPolicy::from);
 
         Ok(Stat {
             name,
+            ppid,
+            pgrp,
+            session,
             state,
             memory_res,
             memory_virtual,
             cpu_used: utime + stime,
+            major_faults,
             num_threads,
+            priority,
+            nice,
+            start_time,
+            flags,
+            rt_priority,
+            policy,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_match_their_documented_positions() -> Result<()> {
+        // Read from a real `cat /proc/self/stat`, with the fields numbered as in proc(5)
+        let stat: Stat = "7519 (cat) R 7514 7519 7514 0 -1 4194304 79 0 0 0 3 2 0 0 20 0 1 0 \
+                          572789 2703360 287 18446744073709551615 93870447185920 \
+                          93870447205801 140727515682688 0 0 0 0 0 0 0 0 0 17 0 0 0 0 0 0 \
+                          93870447221808 93870447223424 93870605000704 140727515686306 \
+                          140727515686326 140727515686326 140727515688939 0"
+            .parse()?;
+
+        assert_eq!(stat.name, "cat");
+        assert_eq!((stat.ppid, stat.pgrp, stat.session), (7514, 7519, 7514));
+        assert_eq!(stat.flags, Some(ProcessFlags(4194304)));
+        assert_eq!(stat.major_faults, 0);
+        // utime (14) + stime (15)
+        assert_eq!(stat.cpu_used, 5);
+        assert_eq!((stat.priority, stat.nice, stat.num_threads), (20, 0, 1));
+        assert_eq!(stat.start_time, 572789);
+        // vsize (23) is in bytes and rss (24) in pages
+        assert_eq!(stat.memory_virtual, 2703360);
+        assert_eq!(stat.memory_res, 287);
+
+        Ok(())
+    }
+
+    #[test]
+    fn scheduling_policy() -> Result<()> {
+        let stat: Stat = "42 (rt) S 1 42 42 0 -1 4194304 0 0 0 0 5 5 0 0 -51 0 1 0 100 4096 2 \
+                          18446744073709551615 0 0 0 0 0 0 0 0 0 0 0 0 17 0 50 1 0 0 0 0 0 0 0"
+            .parse()?;
+        assert_eq!(stat.policy, Some(SchedPolicy::Fifo));
+        assert_eq!(stat.rt_priority, 50);
+
+        // As written by kernels before 2.5.19
+        let stat: Stat = "42 (old) S 1 42 42 0 -1 4194304 0 0 0 0 5 5 0 0 20 0 1 0 100 4096 2 \
+                          18446744073709551615 0 0 0 0 0 0 0 0 0 0 0 0 17 0"
+            .parse()?;
+        assert_eq!(stat.policy, None);
+        assert_eq!(stat.rt_priority, 0);
+
+        Ok(())
+    }
+}
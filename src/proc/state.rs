@@ -1,6 +1,8 @@
 use std::fmt::Display;
 
-#[derive(Debug)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum State {
     Unknown(String),
     Running,
@@ -19,6 +21,17 @@ impl Default for State {
     }
 }
 
+impl State {
+    /// Whether a process in this state is stuck or not running when it should be, which
+    /// is worth pointing out as it happens
+    pub fn needs_attention(&self) -> bool {
+        matches!(
+            self,
+            State::Waiting | State::Zombie | State::Stopped | State::Tracing
+        )
+    }
+}
+
 impl From<&str> for State {
     fn from(value: &str) -> Self {
         match value {
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use super::cgroup::Container;
+
+/// The parts of a process that rarely change, read once and kept while it lives
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct StaticInfo {
+    /// Uptime of the sample the process was last seen in
+    pub(super) uptime: f64,
+    /// Together with the pid identifies the process, since pids are reused
+    pub(super) start_time: u64,
+    /// The kernel's name, which changes when the process execs a new program
+    pub(super) name: String,
+    pub(super) uid: Option<u32>,
+    pub(super) cmdline: String,
+    pub(super) args: Vec<String>,
+    pub(super) container: Option<Container>,
+}
+
+pub(super) trait StaticInfoMap {
+    /// The cached info of `pid`, or `None` if there is none or it belonged to another
+    /// process, either an earlier one with the same pid or the same one before it
+    /// exec'd a new program
+    fn lookup(&mut self, pid: i32, uptime: f64, start_time: u64, name: &str)
+        -> Option<&StaticInfo>;
+    fn cleanup(&mut self, uptime: f64);
+}
+
+impl StaticInfoMap for HashMap<i32, StaticInfo> {
+    fn lookup(
+        &mut self,
+        pid: i32,
+        uptime: f64,
+        start_time: u64,
+        name: &str,
+    ) -> Option<&StaticInfo> {
+        let info = self
+            .get_mut(&pid)
+            .filter(|info| info.start_time == start_time && info.name == name)?;
+        info.uptime = uptime;
+
+        Some(info)
+    }
+
+    fn cleanup(&mut self, uptime: f64) {
+        self.retain(|_, info| info.uptime.eq(&uptime));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reused_pid_is_not_found() {
+        let mut cache = HashMap::default();
+        cache.insert(
+            42,
+            StaticInfo {
+                uptime: 10.0,
+                start_time: 100,
+                name: "old".to_string(),
+                uid: Some(0),
+                cmdline: "old --flag".to_string(),
+                args: vec!["old".to_string(), "--flag".to_string()],
+                container: None,
+            },
+        );
+
+        assert!(cache.lookup(42, 11.0, 100, "old").is_some());
+        assert!(cache.lookup(42, 12.0, 200, "old").is_none());
+        assert!(cache.lookup(42, 12.0, 100, "new").is_none());
+
+        cache.cleanup(12.0);
+        assert!(cache.is_empty());
+    }
+}
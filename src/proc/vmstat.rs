@@ -0,0 +1,95 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Error, Result};
+
+/// The counters used from `/proc/vmstat`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(super) struct VmStat {
+    pswpin: u64,
+    pswpout: u64,
+}
+
+/// Pages swapped per second
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SwapRate {
+    pub pages_in: f32,
+    pub pages_out: f32,
+}
+
+impl FromStr for VmStat {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let err = |message: &str| Error::VmStat {
+            message: message.to_string(),
+            input: input.to_string(),
+        };
+        let mut pswpin = None;
+        let mut pswpout = None;
+
+        for line in input.lines() {
+            let Some((key, value)) = line.split_once(' ') else {
+                continue;
+            };
+            let counter = match key {
+                "pswpin" => &mut pswpin,
+                "pswpout" => &mut pswpout,
+                _ => continue,
+            };
+            *counter = Some(
+                value
+                    .trim()
+                    .parse()
+                    .map_err(|_| err(&format!("Failed to parse {key}")))?,
+            );
+
+            if pswpin.is_some() && pswpout.is_some() {
+                break;
+            }
+        }
+
+        Ok(VmStat {
+            pswpin: pswpin.ok_or_else(|| err("Missing pswpin"))?,
+            pswpout: pswpout.ok_or_else(|| err("Missing pswpout"))?,
+        })
+    }
+}
+
+impl VmStat {
+    /// Rate of swapping since `prev`, which was read `elapsed` seconds earlier
+    pub(super) fn swap_rate(&self, prev: &VmStat, elapsed: f64) -> SwapRate {
+        if elapsed <= 0.0 {
+            return SwapRate::default();
+        }
+
+        let rate = |new: u64, old: u64| (new.saturating_sub(old) as f64 / elapsed) as f32;
+
+        SwapRate {
+            pages_in: rate(self.pswpin, prev.pswpin),
+            pages_out: rate(self.pswpout, prev.pswpout),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swap_rate_between_samples() -> Result<()> {
+        let prev = VmStat::from_str("pgpgin 100\npswpin 10\npswpout 20\npgfault 5\n")?;
+        let new = VmStat::from_str("pgpgin 300\npswpin 40\npswpout 20\npgfault 9\n")?;
+
+        assert_eq!(
+            new.swap_rate(&prev, 1.5),
+            SwapRate {
+                pages_in: 20.0,
+                pages_out: 0.0,
+            }
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use std::{io, path::PathBuf};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Style, Styled, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Paragraph, Widget, Wrap},
+};
+
+use crate::proc::{details::ProcessDetails, process_info::ProcessInfo};
+
+pub struct ProcessDetailWidget<'a> {
+    process: &'a ProcessInfo,
+    /// `None` until the details have been read, or if the source can't read them
+    details: Option<&'a ProcessDetails>,
+}
+
+impl<'a> ProcessDetailWidget<'a> {
+    pub fn new(process: &'a ProcessInfo, details: Option<&'a ProcessDetails>) -> Self {
+        ProcessDetailWidget { process, details }
+    }
+}
+
+impl<'a> Widget for &mut ProcessDetailWidget<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let field = |name: &'static str, value: String| {
+            Line::default().spans(vec![name.into(), value.set_style(Style::default().bold())])
+        };
+        let lines = vec![
+            field("PID: ", self.process.pid.to_string()),
+            field("State: ", self.process.state.to_string()),
+            field(
+                "Flags: ",
+                self.process
+                    .flags
+                    .map_or("-".to_string(), |flags| flags.to_string()),
+            ),
+            field(
+                "Waiting in: ",
+                self.details
+                    .and_then(|details| details.wchan.clone())
+                    .unwrap_or("-".to_string()),
+            ),
+            field(
+                "Executable: ",
+                self.details
+                    .map_or("-".to_string(), |details| link_value(&details.exe)),
+            ),
+            field(
+                "Working directory: ",
+                self.details
+                    .map_or("-".to_string(), |details| link_value(&details.cwd)),
+            ),
+            field("Command: ", self.process.quoted_cmdline()),
+        ];
+
+        let [area] = Layout::horizontal([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(area);
+        let paragraph = Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(format!(" {} ", self.process.name)));
+        // Grow to fit the wrapped command line, which includes the borders
+        let height = paragraph.line_count(area.width) as u16;
+        let [area] = Layout::vertical([Constraint::Length(height)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        Clear.render(area, buf);
+        paragraph.render(area, buf);
+    }
+}
+
+fn link_value(link: &io::Result<PathBuf>) -> String {
+    match link {
+        Ok(path) => path.display().to_string(),
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+            "(permission denied)".to_string()
+        }
+        Err(_) => "-".to_string(),
+    }
+}
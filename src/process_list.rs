@@ -1,29 +1,106 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+use clap::ValueEnum;
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Rect},
     style::{Style, Stylize},
-    widgets::{Row, Table, Widget},
+    text::{Line, Span},
+    widgets::{Cell, Row, StatefulWidget, Table, TableState},
 };
 
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    proc::{process_info::ProcessType, System},
-    utils::human_bytes,
+    column::{cpu_value, Column},
+    proc::{
+        process_info::{ProcessInfo, ProcessType},
+        System,
+    },
+    theme::{highlight_style, memory_style, nice_style, sched_style, severity_style, Theme},
+    tree,
+    utils::{memory_width, MemoryUnit, Precision, UserCache},
 };
 
+/// Widest the Name column grows by default
+pub const DEFAULT_MAX_NAME_WIDTH: u16 = 24;
+
+/// What the Name column shows
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum NameMode {
+    /// The kernel's name for the process, which is cut at 15 characters
+    #[default]
+    Comm,
+    /// [`ProcessInfo::display_name`], the file name of the executable
+    Executable,
+    /// [`ProcessInfo::argv0`], the executable as it was run, usually with its path
+    Path,
+}
+
+impl NameMode {
+    /// The mode after this one, wrapping around
+    pub fn next(self) -> NameMode {
+        match self {
+            NameMode::Comm => NameMode::Executable,
+            NameMode::Executable => NameMode::Path,
+            NameMode::Path => NameMode::Comm,
+        }
+    }
+
+    pub fn name(self, process: &ProcessInfo) -> &str {
+        match self {
+            NameMode::Comm => &process.name,
+            NameMode::Executable => process.display_name(),
+            NameMode::Path => process.argv0(),
+        }
+    }
+}
+
 pub struct ProcessList<'a> {
     current_data: &'a System,
-    usernames: HashMap<u32, String>,
+    columns: &'a [Column],
+    usernames: UserCache,
     show_kernel_threads: bool,
+    privileged_only: bool,
+    collapse_threads: bool,
+    tree: bool,
+    name_mode: NameMode,
+    max_name_width: u16,
+    cpu_totals: HashMap<i32, f32>,
+    search: &'a str,
+    filter: &'a str,
+    invert_filter: bool,
+    env_matches: Option<&'a HashSet<i32>>,
+    marked: Option<&'a HashSet<i32>>,
+    state_flashes: Option<&'a HashMap<i32, u8>>,
+    memory_unit: MemoryUnit,
+    precision: Precision,
+    theme: &'a Theme,
 }
 
 impl<'a> ProcessList<'a> {
-    pub fn new(data: &'a System) -> Self {
+    pub fn new(data: &'a System, theme: &'a Theme) -> Self {
         ProcessList {
             current_data: data,
-            usernames: HashMap::default(),
+            columns: &Column::DEFAULT,
+            usernames: UserCache::default(),
             show_kernel_threads: false,
+            privileged_only: false,
+            collapse_threads: false,
+            tree: false,
+            name_mode: NameMode::Comm,
+            max_name_width: DEFAULT_MAX_NAME_WIDTH,
+            cpu_totals: HashMap::default(),
+            search: "",
+            filter: "",
+            invert_filter: false,
+            env_matches: None,
+            marked: None,
+            state_flashes: None,
+            memory_unit: MemoryUnit::Auto,
+            precision: Precision::default(),
+            theme,
         }
     }
 
@@ -32,88 +109,764 @@ impl<'a> ProcessList<'a> {
 
         self
     }
+
+    /// Only show processes running as root or with capabilities
+    pub fn privileged_only(mut self, privileged_only: bool) -> Self {
+        self.privileged_only = privileged_only;
+
+        self
+    }
+
+    /// Hide thread rows and show the CPU usage of all threads on the process row instead.
+    ///
+    /// Memory isn't summed since the threads of a process share its memory.
+    pub fn collapse_threads(mut self, collapse: bool) -> Self {
+        self.collapse_threads = collapse;
+        self.cpu_totals = if collapse {
+            thread_cpu_totals(&self.current_data.processes, |p| p.cpu_usage)
+        } else {
+            HashMap::default()
+        };
+
+        self
+    }
+
+    /// Show the processes as a tree, with the children of each process below it
+    pub fn tree(mut self, tree: bool) -> Self {
+        self.tree = tree;
+
+        self
+    }
+
+    pub fn name_mode(mut self, name_mode: NameMode) -> Self {
+        self.name_mode = name_mode;
+
+        self
+    }
+
+    /// The Name column grows to fit the longest visible name, up to `width`
+    pub fn max_name_width(mut self, width: u16) -> Self {
+        self.max_name_width = width;
+
+        self
+    }
+
+    pub fn columns(mut self, columns: &'a [Column]) -> Self {
+        self.columns = columns;
+
+        self
+    }
+
+    pub fn search(mut self, search: &'a str) -> Self {
+        self.search = search;
+
+        self
+    }
+
+    /// Only show processes whose name or command contains `filter`
+    pub fn filter(mut self, filter: &'a str) -> Self {
+        self.filter = filter;
+
+        self
+    }
+
+    /// Show the processes not matching the filter instead
+    pub fn invert_filter(mut self, invert: bool) -> Self {
+        self.invert_filter = invert;
+
+        self
+    }
+
+    /// Only show the processes in `matches` when given
+    pub fn env_matches(mut self, matches: Option<&'a HashSet<i32>>) -> Self {
+        self.env_matches = matches;
+
+        self
+    }
+
+    pub fn memory_unit(mut self, unit: MemoryUnit) -> Self {
+        self.memory_unit = unit;
+
+        self
+    }
+
+    pub fn precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+
+        self
+    }
+
+    /// Highlight the processes in `marked`
+    pub fn marked(mut self, marked: &'a HashSet<i32>) -> Self {
+        self.marked = Some(marked);
+
+        self
+    }
+
+    /// Flash the processes in `state_flashes`, which is keyed by pid
+    pub fn state_flashes(mut self, state_flashes: &'a HashMap<i32, u8>) -> Self {
+        self.state_flashes = Some(state_flashes);
+
+        self
+    }
+
+    /// Indices of the visible rows whose name or command matches the search
+    pub fn search_matches(&self) -> Vec<usize> {
+        if self.search.is_empty() {
+            return Vec::default();
+        }
+
+        self.visible_processes()
+            .enumerate()
+            .filter(|(_, p)| matches(p, self.search))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    pub fn visible_processes(&self) -> impl Iterator<Item = &'a ProcessInfo> + '_ {
+        self.visible_rows().into_iter().map(|(p, _)| p)
+    }
+
+    /// The visible processes, with the tree lines to draw in front of their names
+    fn visible_rows(&self) -> Vec<(&'a ProcessInfo, String)> {
+        let visible = self.current_data.processes.iter().filter(|p| {
+            (self.show_kernel_threads || !matches!(p.process_type, ProcessType::KernelThread))
+                && !(self.collapse_threads && matches!(p.process_type, ProcessType::Thread))
+                && (!self.privileged_only || p.is_privileged())
+                && (self.filter.is_empty() || matches(p, self.filter) != self.invert_filter)
+                && self
+                    .env_matches
+                    .is_none_or(|matches| matches.contains(&p.pid))
+        });
+
+        if self.tree {
+            tree::arrange(visible.collect())
+        } else {
+            visible.map(|p| (p, String::new())).collect()
+        }
+    }
+
+    fn cpu_usage(&self, process: &ProcessInfo) -> Option<f32> {
+        if self.collapse_threads {
+            self.cpu_totals.get(&process.pid).copied()
+        } else {
+            process.cpu_usage
+        }
+    }
+
+    /// The configured columns, less the ones that can only be empty on this system
+    fn shown_columns(&self) -> Vec<Column> {
+        // Nothing can be swapped out without any swap
+        let has_swap = self.current_data.mem_usage.swap_total > 0;
+
+        self.columns
+            .iter()
+            .copied()
+            .filter(|column| has_swap || *column != Column::Swap)
+            .collect()
+    }
+}
+
+/// What the text of a row depends on besides the process
+pub struct RowFormat<'a> {
+    pub columns: &'a [Column],
+    pub name_mode: NameMode,
+    pub memory_unit: MemoryUnit,
+    pub precision: Precision,
+}
+
+/// The text of each cell of the row for `process`, in the order of `format.columns`.
+///
+/// `cpu_usage` is shown in the CPU% column rather than the process' own usage, so that
+/// a process can be shown with the usage of all its threads. `tree_prefix` goes in front
+/// of the name.
+pub fn row_cells(
+    process: &ProcessInfo,
+    cpu_usage: Option<f32>,
+    tree_prefix: &str,
+    format: &RowFormat,
+    users: &mut UserCache,
+) -> Vec<String> {
+    format
+        .columns
+        .iter()
+        .map(|column| match column {
+            Column::Name => format!("{tree_prefix}{}", format.name_mode.name(process)),
+            Column::Cpu => cpu_value(cpu_usage, format.precision),
+            column => column.value(process, users, format.memory_unit, format.precision),
+        })
+        .collect()
+}
+
+/// CPU usage of every thread group summed, keyed by the id of the group, using
+/// `cpu_usage` to get the usage of each thread.
+///
+/// Processes without any thread that has a CPU usage yet are left out.
+pub fn thread_cpu_totals(
+    processes: &[ProcessInfo],
+    cpu_usage: impl Fn(&ProcessInfo) -> Option<f32>,
+) -> HashMap<i32, f32> {
+    let mut totals = HashMap::new();
+    for process in processes {
+        if let Some(cpu_usage) = cpu_usage(process) {
+            *totals.entry(process.tgid).or_default() += cpu_usage;
+        }
+    }
+
+    totals
 }
 
-impl<'a> Widget for &mut ProcessList<'a> {
-    fn render(self, area: Rect, buf: &mut Buffer)
+impl<'a> StatefulWidget for &mut ProcessList<'a> {
+    type State = TableState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut TableState)
     where
         Self: Sized,
     {
+        let visible = self.visible_rows();
+        let columns = self.shown_columns();
+        let selected = state
+            .selected()
+            .filter(|_| !visible.is_empty())
+            .map(|selected| selected.min(visible.len() - 1));
+        // Less the header
+        let window = row_window(
+            visible.len(),
+            state.offset(),
+            selected,
+            area.height.saturating_sub(1).into(),
+        );
+
+        // The widths fit every row, not only the ones on screen, so that they don't
+        // change while scrolling
         let mut max_user = 0;
+        // 7 digits fit PID_MAX_LIMIT (4194304), the highest pid_max the kernel allows,
+        // so this only grows for PIDs from a replay or a future kernel with a higher limit
+        let mut max_pid = 7;
+        let mut max_name = Column::Name.header().len();
+        let (mut max_virt, mut max_res, mut max_gpu) = (0, 0, 0);
+        for (p, tree_prefix) in &visible {
+            for column in &columns {
+                match column {
+                    Column::Pid => {
+                        let digits = p.pid.unsigned_abs().checked_ilog10().unwrap_or(0) + 1;
+                        max_pid = max_pid.max(digits as usize);
+                    }
+                    Column::User => {
+                        let user = p.uid.map_or(0, |uid| self.usernames.get(uid).len());
+                        max_user = max_user.max(user);
+                    }
+                    Column::Name => {
+                        let name = self.name_mode.name(p).chars().count();
+                        max_name = max_name.max(tree_prefix.chars().count() + name);
+                    }
+                    Column::Virt => max_virt = max_virt.max(p.virtual_memory),
+                    Column::Res => max_res = max_res.max(p.memory),
+                    Column::GpuMem => {
+                        max_gpu = max_gpu.max(p.gpu_memory.unwrap_or_default() as usize)
+                    }
+                    _ => {}
+                }
+            }
+        }
 
-        let rows: Vec<Row> = self
-            .current_data
-            .processes
+        // Only the rows on screen are built, which matters with tens of thousands of tasks
+        let rows: Vec<Row> = visible[window.clone()]
             .iter()
-            .filter_map(|p| {
+            .enumerate()
+            .map(|(i, (p, tree_prefix))| {
+                let i = window.start + i;
+                // Not on the selected row, where a reversed selection would turn the
+                // stripe into the color of the text
+                let stripe = self
+                    .theme
+                    .stripe
+                    .filter(|_| i % 2 == 1 && selected != Some(i))
+                    .map_or(Style::default(), |color| Style::default().bg(color));
                 let style = match p.process_type {
-                    ProcessType::Task => Style::default().cyan(),
-                    ProcessType::KernelThread => {
-                        if !self.show_kernel_threads {
-                            return None;
-                        }
-                        Style::default().gray()
-                    }
-                    ProcessType::Thread => Style::default(),
+                    ProcessType::Task => stripe.cyan(),
+                    ProcessType::KernelThread => stripe.gray(),
+                    ProcessType::Thread => stripe,
                 };
 
                 let style = if let crate::proc::state::State::Running = p.state {
                     style.bold()
                 } else {
                     style
+                }
+                .patch(nice_style(p.nice))
+                .patch(sched_style(p.sched_policy))
+                .patch(highlight_style(&p.name, &p.cmdline, self.theme))
+                .patch(memory_style(
+                    p.memory,
+                    self.current_data.mem_usage.mem_total,
+                    self.theme,
+                ));
+                let style = if self.marked.is_some_and(|marked| marked.contains(&p.pid)) {
+                    if self.theme.color {
+                        style.yellow()
+                    } else {
+                        style.bold()
+                    }
+                } else {
+                    style
                 };
-
-                let user = if let Some(user) = p.uid {
-                    if let Some(name) = self.usernames.get(&user) {
-                        name.clone()
+                let style = if self
+                    .state_flashes
+                    .is_some_and(|flashes| flashes.contains_key(&p.pid))
+                {
+                    if self.theme.color {
+                        style.on_red()
                     } else {
-                        let name = crate::utils::get_username_from_uid(user).unwrap_or_default();
-                        max_user = max_user.max(name.len());
-                        self.usernames.insert(user, name.clone());
-
-                        name
+                        style.underlined()
                     }
                 } else {
-                    String::default()
+                    style
                 };
-                Some(
-                    Row::new(vec![
-                        format!("{:>7}", p.pid),
-                        user,
-                        p.name.clone(),
-                        human_bytes(p.virtual_memory, true),
-                        human_bytes(p.memory, true),
-                        p.state.to_string(),
-                        format!("{:>5.1}%", p.cpu_usage.unwrap_or_default()),
-                        p.cmdline.clone(),
-                    ])
-                    .style(style),
-                )
+
+                let cpu_usage = self.cpu_usage(p);
+                let format = RowFormat {
+                    columns: &columns,
+                    name_mode: self.name_mode,
+                    memory_unit: self.memory_unit,
+                    precision: self.precision,
+                };
+                let values = row_cells(p, cpu_usage, tree_prefix, &format, &mut self.usernames);
+                let cells: Vec<Cell> = columns
+                    .iter()
+                    .zip(values)
+                    .map(|(column, value)| {
+                        match column {
+                            Column::Name | Column::Command if !self.search.is_empty() => {
+                                highlight_matches(value, self.search).into()
+                            }
+                            // Right aligned, so shorter values line up with a wider one
+                            Column::Pid | Column::Virt | Column::Res | Column::GpuMem => {
+                                Line::from(value).right_aligned().into()
+                            }
+                            Column::Cpu => Cell::from(value)
+                                .style(severity_style(cpu_usage.unwrap_or_default(), self.theme)),
+                            _ => value.into(),
+                        }
+                    })
+                    .collect();
+
+                Row::new(cells).style(style)
             })
             .collect();
 
         max_user = max_user.min(10);
+        // Leave most of a narrow terminal to the other columns
+        let max_name = (max_name as u16)
+            .min(self.max_name_width)
+            .min(area.width / 4);
 
-        let widths = [
-            Constraint::Max(7),
-            Constraint::Max(max_user as u16),
-            Constraint::Max(15),
-            Constraint::Length(8),
-            Constraint::Length(8),
-            Constraint::Length(1),
-            Constraint::Length(6),
-            Constraint::Fill(1),
-        ];
+        let widths: Vec<Constraint> = columns
+            .iter()
+            .map(|column| match column {
+                Column::Pid => Constraint::Max(max_pid as u16),
+                Column::User => Constraint::Max(max_user as u16),
+                Column::Name => Constraint::Length(max_name),
+                Column::Virt => Constraint::Length(memory_width(max_virt, self.memory_unit)),
+                Column::Res => Constraint::Length(memory_width(max_res, self.memory_unit)),
+                Column::GpuMem => Constraint::Length(memory_width(max_gpu, self.memory_unit)),
+                column => column.width(self.precision),
+            })
+            .collect();
 
         Table::new(rows, widths)
             .column_spacing(1)
             .header(
-                Row::new(vec![
-                    "PID", "User", "Name", "Virt", "Res", "S", "CPU%", "Command",
-                ])
-                .style(Style::new().underlined()),
+                Row::new(columns.iter().map(|column| column.header()))
+                    .style(Style::new().underlined()),
             )
-            .row_highlight_style(Style::new().reversed())
-            .render(area, buf);
+            .row_highlight_style(self.theme.selection_style())
+            .render(
+                area,
+                buf,
+                &mut TableState::default().with_selected(
+                    selected
+                        .filter(|i| window.contains(i))
+                        .map(|i| i - window.start),
+                ),
+            );
+
+        *state.offset_mut() = window.start;
+        state.select(selected);
+    }
+}
+
+/// The rows shown out of `len` rows when `height` fit, starting at `offset` unless the
+/// `selected` row is outside of that. The same as the rows a [`Table`] shows.
+fn row_window(
+    len: usize,
+    offset: usize,
+    selected: Option<usize>,
+    height: usize,
+) -> std::ops::Range<usize> {
+    let mut start = offset.min(len.saturating_sub(1));
+    // No room for any row, so there is no selection to scroll to either
+    if height == 0 {
+        return start..start;
+    }
+    match selected {
+        Some(selected) if selected >= start + height => {
+            start = (selected + 1).saturating_sub(height)
+        }
+        Some(selected) if selected < start => start = selected,
+        _ => {}
+    }
+
+    start..(start + height).min(len)
+}
+
+fn matches(process: &ProcessInfo, needle: &str) -> bool {
+    !match_ranges(&process.name, needle).is_empty()
+        || !match_ranges(&process.cmdline, needle).is_empty()
+}
+
+/// Byte ranges of all case-insensitive matches of `needle` in `haystack`
+fn match_ranges(haystack: &str, needle: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    if needle.is_empty() {
+        return ranges;
+    }
+
+    let mut start = 0;
+    while start < haystack.len() {
+        let end = start + needle.len();
+        if haystack
+            .get(start..end)
+            .is_some_and(|s| s.eq_ignore_ascii_case(needle))
+        {
+            ranges.push((start, end));
+            start = end;
+        } else {
+            start += haystack[start..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+
+    ranges
+}
+
+fn highlight_matches(value: String, search: &str) -> Line<'static> {
+    let ranges = match_ranges(&value, search);
+    if ranges.is_empty() {
+        return value.into();
+    }
+
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    for (start, end) in ranges {
+        if start > pos {
+            spans.push(Span::raw(value[pos..start].to_string()));
+        }
+        spans.push(Span::styled(
+            value[start..end].to_string(),
+            Style::new().black().on_yellow(),
+        ));
+        pos = end;
+    }
+    if pos < value.len() {
+        spans.push(Span::raw(value[pos..].to_string()));
+    }
+
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::{Color, Modifier};
+
+    use super::*;
+
+    #[test]
+    fn match_ranges_ignores_case() {
+        assert_eq!(
+            match_ranges("Firefox --new-FIRE", "fire"),
+            vec![(0, 4), (14, 18)]
+        );
+        assert!(match_ranges("bash", "zsh").is_empty());
+        assert_eq!(match_ranges("åfire", "FIRE"), vec![(2, 6)]);
+    }
+
+    #[test]
+    fn collapsed_threads_sum_cpu() {
+        let thread = |pid, process_type, cpu_usage| ProcessInfo {
+            pid,
+            tgid: 100,
+            process_type,
+            cpu_usage: Some(cpu_usage),
+            ..Default::default()
+        };
+        let system = System {
+            processes: vec![
+                thread(100, ProcessType::Task, 1.5),
+                thread(101, ProcessType::Thread, 20.0),
+                thread(102, ProcessType::Thread, 30.0),
+            ],
+            ..Default::default()
+        };
+        let theme = Theme::default();
+
+        let list = ProcessList::new(&system, &theme).collapse_threads(true);
+        let visible: Vec<&ProcessInfo> = list.visible_processes().collect();
+        assert_eq!(visible.len(), 1);
+        assert_eq!(list.cpu_usage(visible[0]), Some(51.5));
+
+        let list = ProcessList::new(&system, &theme);
+        assert_eq!(list.visible_processes().count(), 3);
+        assert_eq!(list.cpu_usage(&system.processes[0]), Some(1.5));
+    }
+
+    #[test]
+    fn inverted_filter_shows_the_rest() {
+        let process = |pid, name: &str| ProcessInfo {
+            pid,
+            tgid: pid,
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let system = System {
+            processes: vec![process(1, "bash"), process(2, "chrome"), process(3, "vim")],
+            ..Default::default()
+        };
+        let theme = Theme::default();
+        let pids = |list: ProcessList| list.visible_processes().map(|p| p.pid).collect::<Vec<_>>();
+
+        assert_eq!(
+            pids(ProcessList::new(&system, &theme).filter("chrome")),
+            [2]
+        );
+        let inverted = ProcessList::new(&system, &theme)
+            .filter("chrome")
+            .invert_filter(true);
+        assert_eq!(pids(inverted), [1, 3]);
+        // Without a filter there is nothing to invert
+        let inverted = ProcessList::new(&system, &theme).invert_filter(true);
+        assert_eq!(pids(inverted), [1, 2, 3]);
+    }
+
+    #[test]
+    fn every_other_row_is_striped() {
+        let system = System {
+            processes: (1..=3)
+                .map(|pid| ProcessInfo {
+                    pid,
+                    tgid: pid,
+                    process_type: ProcessType::Thread,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let theme = Theme {
+            stripe: Some(Color::Blue),
+            ..Theme::default()
+        };
+        let area = Rect::new(0, 0, 40, 5);
+        let mut buf = Buffer::empty(area);
+        let mut state = TableState::default().with_selected(2);
+        ProcessList::new(&system, &theme).render(area, &mut buf, &mut state);
+
+        // Row 0 is the header
+        let bg = |buf: &Buffer, row: u16| buf[(0, row + 1)].bg;
+        assert_eq!(bg(&buf, 0), Color::Reset);
+        assert_eq!(bg(&buf, 1), Color::Blue);
+        assert_eq!(bg(&buf, 2), Color::Reset);
+
+        let mut buf = Buffer::empty(area);
+        let mut state = TableState::default().with_selected(1);
+        ProcessList::new(&system, &theme).render(area, &mut buf, &mut state);
+        assert_eq!(bg(&buf, 1), Color::Reset);
+        assert!(buf[(0, 2)].modifier.contains(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn only_the_rows_on_screen_are_built() {
+        let system = System {
+            processes: (1..=50_000)
+                .map(|pid| ProcessInfo {
+                    pid,
+                    tgid: pid,
+                    name: format!("proc-{pid}"),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let theme = Theme::default();
+        let area = Rect::new(0, 0, 80, 21);
+        let mut buf = Buffer::empty(area);
+        let columns = [Column::Pid, Column::Name];
+        let mut state = TableState::default().with_selected(30_000);
+        ProcessList::new(&system, &theme)
+            .columns(&columns)
+            .render(area, &mut buf, &mut state);
+
+        // The selection is scrolled to the bottom row, like a table with every row would
+        assert_eq!(state.offset(), 30_000 - 19);
+        assert_eq!(state.selected(), Some(30_000));
+        let line = |row: u16| {
+            (0..area.width)
+                .map(|x| buf[(x, row)].symbol())
+                .collect::<String>()
+        };
+        assert!(line(1).starts_with("  29982 proc-29982"), "{:?}", line(1));
+        assert!(line(20).starts_with("  30001 proc-30001"), "{:?}", line(20));
+    }
+
+    #[test]
+    fn swap_column_is_hidden_without_swap() {
+        let mut system = System {
+            processes: vec![ProcessInfo {
+                pid: 1,
+                tgid: 1,
+                name: "init".to_string(),
+                swap: Some(4096),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let theme = Theme::default();
+        let area = Rect::new(0, 0, 40, 2);
+        let columns = [Column::Pid, Column::Swap, Column::Name];
+        let header = |system: &System| {
+            let mut buf = Buffer::empty(area);
+            ProcessList::new(system, &theme).columns(&columns).render(
+                area,
+                &mut buf,
+                &mut TableState::default(),
+            );
+
+            (0..area.width)
+                .map(|x| buf[(x, 0)].symbol())
+                .collect::<String>()
+        };
+
+        assert!(header(&system).starts_with("PID     Name"));
+        system.mem_usage.swap_total = 1024 * 1024;
+        assert!(header(&system).starts_with("PID     Swp Name"));
+    }
+
+    #[test]
+    fn row_window_follows_the_selection() {
+        assert_eq!(row_window(100, 0, Some(5), 10), 0..10);
+        assert_eq!(row_window(100, 0, Some(15), 10), 6..16);
+        assert_eq!(row_window(100, 50, Some(15), 10), 15..25);
+        assert_eq!(row_window(100, 95, None, 10), 95..100);
+        // The offset is kept within the rows
+        assert_eq!(row_window(3, 10, None, 10), 2..3);
+        assert_eq!(row_window(0, 0, None, 10), 0..0);
+        assert_eq!(row_window(100, 50, Some(5), 0), 50..50);
+    }
+
+    #[test]
+    fn header_only_area_shows_no_rows() {
+        let system = System {
+            processes: (1..=3)
+                .map(|pid| ProcessInfo {
+                    pid,
+                    tgid: pid,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let theme = Theme::default();
+        let area = Rect::new(0, 0, 40, 1);
+        let mut buf = Buffer::empty(area);
+        let mut state = TableState::default().with_selected(0);
+
+        ProcessList::new(&system, &theme).render(area, &mut buf, &mut state);
+
+        assert_eq!(state.selected(), Some(0));
+    }
+
+    #[test]
+    fn wide_pids_stay_aligned() {
+        // Wider than any PID the kernel hands out today
+        let system = System {
+            processes: [42, 12_345_678]
+                .into_iter()
+                .map(|pid| ProcessInfo {
+                    pid,
+                    tgid: pid,
+                    name: "x".to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let theme = Theme::default();
+        let area = Rect::new(0, 0, 60, 3);
+        let mut buf = Buffer::empty(area);
+        let columns = [Column::Pid, Column::Name];
+        ProcessList::new(&system, &theme).columns(&columns).render(
+            area,
+            &mut buf,
+            &mut TableState::default(),
+        );
+
+        let line = |row: u16| {
+            (0..area.width)
+                .map(|x| buf[(x, row)].symbol())
+                .collect::<String>()
+        };
+        assert!(line(1).starts_with("      42 x"), "{:?}", line(1));
+        assert!(line(2).starts_with("12345678 x"), "{:?}", line(2));
+    }
+
+    #[test]
+    fn cells_of_a_known_process() {
+        let process = ProcessInfo {
+            pid: 42,
+            uid: Some(0),
+            name: "kworker".to_string(),
+            state: "S".into(),
+            memory: 2 * 1024 * 1024,
+            virtual_memory: 1536,
+            cmdline: "/usr/bin/sleep 60".to_string(),
+            args: vec!["/usr/bin/sleep".to_string(), "60".to_string()],
+            ..Default::default()
+        };
+        let mut format = RowFormat {
+            columns: &Column::DEFAULT,
+            name_mode: NameMode::Comm,
+            memory_unit: MemoryUnit::Auto,
+            precision: Precision::default(),
+        };
+        let mut users = UserCache::default();
+
+        assert_eq!(
+            row_cells(&process, Some(12.34), "", &format, &mut users),
+            [
+                "     42",
+                "root",
+                "kworker",
+                "   1.50k",
+                "   2.00M",
+                "S",
+                " 12.3%",
+                "/usr/bin/sleep 60",
+            ]
+        );
+
+        format.name_mode = NameMode::Executable;
+        let cells = row_cells(&process, None, "└─ ", &format, &mut users);
+        assert_eq!(cells[2], "└─ sleep");
+
+        format.name_mode = NameMode::Path;
+        let cells = row_cells(&process, None, "", &format, &mut users);
+        assert_eq!(cells[2], "/usr/bin/sleep");
+        assert_eq!(cells[6], format!("{:>6}", "—"));
+
+        let kernel_thread = ProcessInfo {
+            name: "kworker/0:1".to_string(),
+            process_type: ProcessType::KernelThread,
+            ..Default::default()
+        };
+        let cells = row_cells(&kernel_thread, None, "", &format, &mut users);
+        assert_eq!(cells[7], "[kworker/0:1]");
     }
 }
@@ -0,0 +1,105 @@
+//! Recording samples to a file and playing them back instead of reading `/proc`.
+//!
+//! Recordings are JSON lines, one sample per line together with the time it was taken
+//! relative to the start of the recording.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{proc::System, Message};
+
+#[derive(Serialize, Deserialize)]
+struct Sample<S> {
+    time: Duration,
+    system: S,
+}
+
+pub struct Recorder {
+    file: BufWriter<File>,
+    start: Instant,
+}
+
+impl Recorder {
+    pub fn new(file: File) -> Self {
+        Recorder {
+            file: BufWriter::new(file),
+            start: Instant::now(),
+        }
+    }
+
+    pub fn record(&mut self, system: &System) -> io::Result<()> {
+        let sample = Sample {
+            time: self.start.elapsed(),
+            system,
+        };
+        serde_json::to_writer(&mut self.file, &sample)?;
+        self.file.write_all(b"\n")?;
+        // Flush every sample so the recording is usable even if fell is killed
+        self.file.flush()
+    }
+}
+
+/// Play back a recording made with [`Recorder`], sending the samples at the same pace
+/// they were recorded. The last sample stays on screen once the recording ends.
+pub fn start_thread(
+    tx: mpsc::Sender<Message>,
+    rx: mpsc::Receiver<Message>,
+    file: File,
+) -> io::Result<JoinHandle<()>> {
+    thread::Builder::new()
+        .name("fell-replay".to_string())
+        .spawn(move || thread_main(tx, rx, file))
+}
+
+fn thread_main(tx: mpsc::Sender<Message>, rx: mpsc::Receiver<Message>, file: File) {
+    let start = Instant::now();
+    let mut lines = BufReader::new(file).lines();
+
+    loop {
+        let sample = match lines.next() {
+            Some(Ok(line)) => match serde_json::from_str::<Sample<System>>(&line) {
+                Ok(sample) => Some(sample),
+                Err(err) => {
+                    log::error!("Skipping invalid sample in recording: {err}");
+                    continue;
+                }
+            },
+            Some(Err(err)) => {
+                log::error!("Failed to read recording: {err}");
+                None
+            }
+            None => None,
+        };
+
+        let Some(sample) = sample else {
+            // Nothing more to play, just wait to be told to quit
+            while let Ok(msg) = rx.recv() {
+                if let Message::Quit = msg {
+                    break;
+                }
+            }
+            return;
+        };
+
+        let deadline = start + sample.time;
+        loop {
+            match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(Message::Quit) | Err(RecvTimeoutError::Disconnected) => return,
+                // Nothing to configure when not reading from /proc
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) => break,
+            }
+        }
+
+        if tx.send(Message::SysInfo(sample.system)).is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::{env, fs, path::Path, path::PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::process_list::NameMode;
+
+/// What fell remembers between runs of the choices made while running, as opposed to the
+/// config, which is only ever written by the user
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedState {
+    /// What the Name column showed when it was last changed with 'a', used instead of
+    /// the config's `name_mode`
+    pub name_mode: Option<NameMode>,
+}
+
+impl SavedState {
+    /// Load the state saved at `path`. Starts over with nothing remembered if there is
+    /// no state yet or it can't be read, since it is only a convenience.
+    pub fn load(path: &Path) -> Self {
+        let Ok(input) = fs::read_to_string(path) else {
+            return SavedState::default();
+        };
+
+        toml::from_str(&input)
+            .inspect_err(|err| log::warn!("Ignoring state file {}: {err}", path.display()))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let output = toml::to_string(self).context("Failed to serialize the state")?;
+
+        fs::write(path, output)
+            .with_context(|| format!("Failed to write state file {}", path.display()))
+    }
+}
+
+/// `fell/state.toml` in the XDG state directory
+pub fn default_path() -> Option<PathBuf> {
+    let state_dir = env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+
+    Some(state_dir.join("fell").join("state.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_state_is_loaded_again() -> anyhow::Result<()> {
+        let path = env::temp_dir()
+            .join(format!("fell-test-state-{}", std::process::id()))
+            .join("state.toml");
+        assert_eq!(SavedState::load(&path), SavedState::default());
+
+        let state = SavedState {
+            name_mode: Some(NameMode::Path),
+        };
+        state.save(&path)?;
+        let loaded = SavedState::load(&path);
+        fs::remove_dir_all(path.parent().unwrap())?;
+
+        assert_eq!(loaded, state);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,243 @@
+use std::{cmp::Ordering, str::FromStr};
+
+use clap::ValueEnum;
+
+use crate::proc::process_info::ProcessInfo;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    Pid,
+    Name,
+    Virt,
+    #[value(alias = "mem")]
+    Res,
+    #[default]
+    Cpu,
+    Command,
+}
+
+/// Something rows can be sorted by, such as a column of the process list
+pub trait Key: Copy + PartialEq + 'static {
+    /// Every key, in the order they are cycled through
+    const ALL: &'static [Self];
+
+    /// Header of the column the key sorts by
+    fn header(&self) -> &'static str;
+
+    /// The direction the key sorts in unless it is reversed
+    fn default_direction(&self) -> Direction;
+
+    fn next(&self) -> Self {
+        let i = Self::ALL
+            .iter()
+            .position(|key| key == self)
+            .unwrap_or_default();
+
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    fn previous(&self) -> Self {
+        let i = Self::ALL
+            .iter()
+            .position(|key| key == self)
+            .unwrap_or_default();
+
+        Self::ALL[(i + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
+
+impl SortKey {
+    /// Order `a` before `b` if it should be shown above it in the key's default direction.
+    /// Numbers sort with the largest first and text alphabetically.
+    pub fn compare(&self, a: &ProcessInfo, b: &ProcessInfo) -> Ordering {
+        match self {
+            SortKey::Pid => a.pid.cmp(&b.pid),
+            SortKey::Name => a.name.cmp(&b.name),
+            SortKey::Virt => b.virtual_memory.cmp(&a.virtual_memory),
+            SortKey::Res => b.memory.cmp(&a.memory),
+            SortKey::Cpu => b
+                .sort_cpu_usage()
+                .unwrap_or_default()
+                .total_cmp(&a.sort_cpu_usage().unwrap_or_default()),
+            SortKey::Command => a.cmdline.cmp(&b.cmdline),
+        }
+    }
+}
+
+impl Key for SortKey {
+    const ALL: &'static [SortKey] = &[
+        SortKey::Pid,
+        SortKey::Name,
+        SortKey::Virt,
+        SortKey::Res,
+        SortKey::Cpu,
+        SortKey::Command,
+    ];
+
+    fn header(&self) -> &'static str {
+        match self {
+            SortKey::Pid => "PID",
+            SortKey::Name => "Name",
+            SortKey::Virt => "Virt",
+            SortKey::Res => "Res",
+            SortKey::Cpu => "CPU%",
+            SortKey::Command => "Command",
+        }
+    }
+
+    /// The direction [`SortKey::compare`] sorts in
+    fn default_direction(&self) -> Direction {
+        match self {
+            SortKey::Virt | SortKey::Res | SortKey::Cpu => Direction::Descending,
+            _ => Direction::Ascending,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Ascending,
+    Descending,
+}
+
+impl Direction {
+    pub fn reversed(&self) -> Self {
+        match self {
+            Direction::Ascending => Direction::Descending,
+            Direction::Descending => Direction::Ascending,
+        }
+    }
+
+    pub fn arrow(&self) -> &'static str {
+        match self {
+            Direction::Ascending => "↑",
+            Direction::Descending => "↓",
+        }
+    }
+}
+
+/// What to sort rows by. For the process list this is given on the command line as
+/// `key[:asc|:desc]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortSpec<K = SortKey> {
+    pub key: K,
+    pub direction: Direction,
+}
+
+impl<K: Key> SortSpec<K> {
+    /// Sort by `key` in its default direction
+    pub fn new(key: K) -> Self {
+        SortSpec {
+            key,
+            direction: key.default_direction(),
+        }
+    }
+
+    /// Turn `ordering`, which is in the key's default direction, around if needed
+    pub fn directed(&self, ordering: Ordering) -> Ordering {
+        if self.direction == self.key.default_direction() {
+            ordering
+        } else {
+            ordering.reverse()
+        }
+    }
+}
+
+impl SortSpec {
+    /// Order `a` before `b` if it should be shown above it, with ties broken by PID so
+    /// that the order stays the same between refreshes
+    pub fn compare(&self, a: &ProcessInfo, b: &ProcessInfo) -> Ordering {
+        self.directed(self.key.compare(a, b))
+            .then_with(|| a.pid.cmp(&b.pid))
+    }
+}
+
+impl<K: Key + Default> Default for SortSpec<K> {
+    fn default() -> Self {
+        SortSpec::new(K::default())
+    }
+}
+
+impl FromStr for SortSpec {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (key, direction) = match input.split_once(':') {
+            Some((key, direction)) => (key, Some(direction)),
+            None => (input, None),
+        };
+        let key = SortKey::from_str(key, true).map_err(|_| {
+            let keys: Vec<String> = SortKey::value_variants()
+                .iter()
+                .filter_map(|key| key.to_possible_value())
+                .map(|value| value.get_name().to_string())
+                .collect();
+            format!(
+                "unknown sort key '{key}', expected one of: {}",
+                keys.join(", ")
+            )
+        })?;
+        let direction = match direction {
+            None => key.default_direction(),
+            Some("asc") => Direction::Ascending,
+            Some("desc") => Direction::Descending,
+            Some(direction) => {
+                return Err(format!(
+                    "unknown sort direction '{direction}', expected asc or desc"
+                ))
+            }
+        };
+
+        Ok(SortSpec { key, direction })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ties_are_ordered_by_pid() {
+        let process = |pid, cpu_usage| ProcessInfo {
+            pid,
+            cpu_usage: Some(cpu_usage),
+            ..Default::default()
+        };
+        let mut processes = [
+            process(30, 0.0),
+            process(10, 0.0),
+            process(20, 5.0),
+            process(5, 0.0),
+        ];
+
+        processes.sort_by(|a, b| SortSpec::new(SortKey::Cpu).compare(a, b));
+
+        let pids: Vec<i32> = processes.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![20, 5, 10, 30]);
+
+        processes.sort_by(|a, b| "cpu:asc".parse::<SortSpec>().unwrap().compare(a, b));
+
+        let pids: Vec<i32> = processes.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![5, 10, 30, 20]);
+    }
+
+    #[test]
+    fn parse_sort_spec() {
+        assert_eq!("cpu".parse(), Ok(SortSpec::new(SortKey::Cpu)));
+        assert_eq!(
+            "mem:asc".parse(),
+            Ok(SortSpec {
+                key: SortKey::Res,
+                direction: Direction::Ascending,
+            })
+        );
+        assert_eq!(
+            "foo:desc".parse::<SortSpec>(),
+            Err(
+                "unknown sort key 'foo', expected one of: pid, name, virt, res, cpu, command"
+                    .to_string()
+            )
+        );
+        assert!("cpu:up".parse::<SortSpec>().is_err());
+    }
+}
@@ -0,0 +1,153 @@
+use std::fs;
+
+use crate::proc::{details::ProcessDetails, Proc, Result, System};
+
+/// Information about the host that doesn't change between samples
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HostInfo {
+    pub hostname: String,
+    pub kernel_release: String,
+}
+
+/// Where samples of the system come from.
+///
+/// The UI only talks to a source through this trait, so that a backend for another
+/// platform can be added without touching it. [`Proc`] is the Linux implementation.
+pub trait SystemSource {
+    /// Read a new sample, including every thread when `get_threads` is set
+    fn sample(&mut self, get_threads: bool) -> Result<System>;
+
+    fn host_info(&self) -> HostInfo;
+
+    /// Forget the previous per-process usage, e.g. when switching between reading
+    /// processes and threads
+    fn reset_prev_cpus(&mut self);
+
+    /// Forget everything remembered from previous samples, so that the next sample has
+    /// no usage or rates, as if it was the first one
+    fn reset_baselines(&mut self);
+
+    /// Count the open file descriptors of every process, if the source can
+    fn read_fds(&mut self, _read: bool) {}
+
+    /// Read the syscall counts of every process, if the source can
+    fn read_io(&mut self, _read: bool) {}
+
+    /// Read the GPU memory of every process, if the source can
+    fn read_gpu_memory(&mut self, _read: bool) {}
+
+    /// Read how much memory of every process is swapped out, if the source can
+    fn read_swap(&mut self, _read: bool) {}
+
+    /// Read the effective capabilities of every process, if the source can
+    fn read_caps(&mut self, _read: bool) {}
+
+    /// Read the environment variables of every process, if the source can
+    fn read_environ(&mut self, _read: bool) {}
+
+    /// Read the details of a single process, `None` if the source can't
+    fn process_details(&self, _pid: i32) -> Option<ProcessDetails> {
+        None
+    }
+}
+
+impl<S: SystemSource + ?Sized> SystemSource for &mut S {
+    fn sample(&mut self, get_threads: bool) -> Result<System> {
+        (**self).sample(get_threads)
+    }
+
+    fn host_info(&self) -> HostInfo {
+        (**self).host_info()
+    }
+
+    fn reset_prev_cpus(&mut self) {
+        (**self).reset_prev_cpus();
+    }
+
+    fn reset_baselines(&mut self) {
+        (**self).reset_baselines();
+    }
+
+    fn read_fds(&mut self, read: bool) {
+        (**self).read_fds(read);
+    }
+
+    fn read_io(&mut self, read: bool) {
+        (**self).read_io(read);
+    }
+
+    fn read_gpu_memory(&mut self, read: bool) {
+        (**self).read_gpu_memory(read);
+    }
+
+    fn read_swap(&mut self, read: bool) {
+        (**self).read_swap(read);
+    }
+
+    fn read_caps(&mut self, read: bool) {
+        (**self).read_caps(read);
+    }
+
+    fn read_environ(&mut self, read: bool) {
+        (**self).read_environ(read);
+    }
+
+    fn process_details(&self, pid: i32) -> Option<ProcessDetails> {
+        (**self).process_details(pid)
+    }
+}
+
+impl SystemSource for Proc {
+    fn sample(&mut self, get_threads: bool) -> Result<System> {
+        self.get_system(get_threads)
+    }
+
+    fn host_info(&self) -> HostInfo {
+        let read = |name: &str| {
+            fs::read_to_string(self.path().join("sys/kernel").join(name))
+                .map(|value| value.trim().to_string())
+                .unwrap_or_default()
+        };
+
+        HostInfo {
+            hostname: read("hostname"),
+            kernel_release: read("osrelease"),
+        }
+    }
+
+    fn reset_prev_cpus(&mut self) {
+        Proc::reset_prev_cpus(self);
+    }
+
+    fn reset_baselines(&mut self) {
+        Proc::reset_baselines(self);
+    }
+
+    fn read_fds(&mut self, read: bool) {
+        Proc::read_fds(self, read);
+    }
+
+    fn read_io(&mut self, read: bool) {
+        Proc::read_io(self, read);
+    }
+
+    fn read_gpu_memory(&mut self, read: bool) {
+        Proc::read_gpu_memory(self, read);
+    }
+
+    fn read_swap(&mut self, read: bool) {
+        Proc::read_swap(self, read);
+    }
+
+    fn read_caps(&mut self, read: bool) {
+        Proc::read_caps(self, read);
+    }
+
+    fn read_environ(&mut self, read: bool) {
+        Proc::read_environ(self, read);
+    }
+
+    fn process_details(&self, pid: i32) -> Option<ProcessDetails> {
+        Some(ProcessDetails::read(self.path(), pid))
+    }
+}
@@ -0,0 +1,251 @@
+use std::{fmt::Write, str::FromStr, thread, time::Duration};
+
+use fell::{
+    proc::{self, System},
+    SystemSource,
+};
+
+use crate::utils::{human_bytes_fixed_unit, MemoryUnit};
+
+pub const DEFAULT_FORMAT: &str = "up {uptime} | load {load} | mem {mem} | cpu {cpu}";
+
+/// Time between the two samples CPU usage is calculated from
+const CPU_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A value that can be placed in the status line with `{name}`
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Uptime,
+    Load,
+    Load1,
+    Load5,
+    Load15,
+    Cpu,
+    Mem,
+    MemUsed,
+    MemTotal,
+    Swap,
+    Tasks,
+    Threads,
+}
+
+impl Field {
+    const ALL: [(&'static str, Field); 12] = [
+        ("uptime", Field::Uptime),
+        ("load", Field::Load),
+        ("load1", Field::Load1),
+        ("load5", Field::Load5),
+        ("load15", Field::Load15),
+        ("cpu", Field::Cpu),
+        ("mem", Field::Mem),
+        ("mem_used", Field::MemUsed),
+        ("mem_total", Field::MemTotal),
+        ("swap", Field::Swap),
+        ("tasks", Field::Tasks),
+        ("threads", Field::Threads),
+    ];
+
+    fn write(self, out: &mut String, system: &System, memory_unit: MemoryUnit) {
+        let mem = &system.mem_usage;
+        let percent = |used: usize, total: usize| {
+            if total == 0 {
+                0.0
+            } else {
+                used as f32 / total as f32 * 100.0
+            }
+        };
+        let load = &system.load_avg;
+        let _ = match self {
+            Field::Uptime => write!(out, "{}", compact_duration(system.uptime)),
+            Field::Load => write!(out, "{:.2} {:.2} {:.2}", load.one, load.five, load.fifteen),
+            Field::Load1 => write!(out, "{:.2}", load.one),
+            Field::Load5 => write!(out, "{:.2}", load.five),
+            Field::Load15 => write!(out, "{:.2}", load.fifteen),
+            Field::Cpu => match &system.cpu_usage {
+                Some(usage) => write!(out, "{:.0}%", usage.total),
+                None => write!(out, "-"),
+            },
+            Field::Mem => write!(out, "{:.0}%", percent(mem.mem_used(), mem.mem_total)),
+            Field::MemUsed => write!(
+                out,
+                "{}",
+                human_bytes_fixed_unit(mem.mem_used(), memory_unit, false)
+            ),
+            Field::MemTotal => write!(
+                out,
+                "{}",
+                human_bytes_fixed_unit(mem.mem_total, memory_unit, false)
+            ),
+            Field::Swap => write!(out, "{:.0}%", percent(mem.swap_used(), mem.swap_total)),
+            Field::Tasks => write!(out, "{}", system.num_threads.tasks),
+            Field::Threads => write!(out, "{}", system.num_threads.threads),
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    Field(Field),
+}
+
+/// Format of the one line printed by `--status`, plain text with fields like `{cpu}`
+/// substituted. `{{` and `}}` print literal braces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusFormat {
+    segments: Vec<Segment>,
+}
+
+impl StatusFormat {
+    fn needs_cpu(&self) -> bool {
+        self.segments.contains(&Segment::Field(Field::Cpu))
+    }
+
+    pub fn format(&self, system: &System, memory_unit: MemoryUnit) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => out.push_str(text),
+                Segment::Field(field) => field.write(&mut out, system, memory_unit),
+            }
+        }
+
+        out
+    }
+}
+
+impl Default for StatusFormat {
+    fn default() -> Self {
+        DEFAULT_FORMAT
+            .parse()
+            .expect("default status format is valid")
+    }
+}
+
+impl FromStr for StatusFormat {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = input.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest
+                        .find('}')
+                        .ok_or_else(|| format!("unclosed '{{' in status format '{input}'"))?;
+                    let name = &rest[..end];
+                    let field = Field::ALL
+                        .iter()
+                        .find(|(field_name, _)| *field_name == name)
+                        .map(|(_, field)| *field)
+                        .ok_or_else(|| {
+                            let names: Vec<_> = Field::ALL.iter().map(|(name, _)| *name).collect();
+                            format!(
+                                "unknown status field '{name}', expected one of: {}",
+                                names.join(", ")
+                            )
+                        })?;
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(Segment::Field(field));
+                    chars = rest[end + 1..].chars();
+                }
+                '}' => return Err(format!("unmatched '}}' in status format '{input}'")),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+
+        Ok(StatusFormat { segments })
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for StatusFormat {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let input = String::deserialize(deserializer)?;
+        input.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Take a sample of the system and format it as one line. CPU usage needs a second
+/// sample, so it is only waited for when the format shows it.
+pub fn status_line(
+    source: &mut impl SystemSource,
+    format: &StatusFormat,
+    memory_unit: MemoryUnit,
+) -> proc::Result<String> {
+    let mut system = source.sample(false)?;
+    if format.needs_cpu() {
+        thread::sleep(CPU_INTERVAL);
+        system = source.sample(false)?;
+    }
+
+    Ok(format.format(&system, memory_unit))
+}
+
+/// Like [`crate::utils::human_duration`] but short enough for a status bar, e.g. "3d 04:12"
+fn compact_duration(duration: Duration) -> String {
+    let mins = duration.as_secs() / 60;
+    let days = mins / (24 * 60);
+    let hours = mins / 60 % 24;
+    let mins = mins % 60;
+
+    if days > 0 {
+        format!("{days}d {hours:02}:{mins:02}")
+    } else {
+        format!("{hours:02}:{mins:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fell::proc::{CpuUsage, LoadAvg, MemInfo};
+
+    use super::*;
+
+    #[test]
+    fn formats_the_chosen_fields() {
+        let system = System {
+            uptime: Duration::from_secs(3 * 86400 + 4 * 3600 + 12 * 60 + 5),
+            load_avg: LoadAvg {
+                one: 1.2,
+                five: 0.9,
+                fifteen: 0.8,
+            },
+            cpu_usage: Some(CpuUsage {
+                total: 18.2,
+                cores: Vec::new(),
+            }),
+            mem_usage: MemInfo {
+                mem_total: 100,
+                mem_free: 58,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let format = StatusFormat::default();
+        assert_eq!(
+            format.format(&system, MemoryUnit::Auto),
+            "up 3d 04:12 | load 1.20 0.90 0.80 | mem 42% | cpu 18%"
+        );
+        let format: StatusFormat = "{{{cpu}}} {load1}".parse().unwrap();
+        assert_eq!(format.format(&system, MemoryUnit::Auto), "{18%} 1.20");
+        assert!("{bogus}".parse::<StatusFormat>().is_err());
+        assert!("{cpu".parse::<StatusFormat>().is_err());
+    }
+}
@@ -1,31 +1,240 @@
-use std::{io, sync::mpsc, thread, time::Duration};
+use std::{
+    io,
+    sync::mpsc::{self, RecvTimeoutError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
 
-use crate::{proc::Proc, Message};
+use fell::SystemSource;
 
-pub fn start_thread(tx: mpsc::Sender<Message>, rx: mpsc::Receiver<Message>) -> io::Result<()> {
+use crate::{replay::Recorder, Message};
+
+/// Time between samples
+pub const INTERVAL: Duration = Duration::from_millis(1_500);
+
+/// Time between the sample taken at startup and the first one sent, long enough for the
+/// CPU usage calculated from them to be meaningful
+const WARM_UP: Duration = Duration::from_millis(250);
+
+pub fn start_thread(
+    tx: mpsc::Sender<Message>,
+    rx: mpsc::Receiver<Message>,
+    source: impl SystemSource + Send + 'static,
+    recorder: Option<Recorder>,
+    send_threads: bool,
+) -> io::Result<JoinHandle<()>> {
     thread::Builder::new()
         .name("fell-sysinfo".to_string())
-        .spawn(move || thread_main(tx, rx))?;
-
-    Ok(())
+        .spawn(move || thread_main(tx, rx, source, recorder, send_threads, WARM_UP))
 }
 
-fn thread_main(tx: mpsc::Sender<Message>, rx: mpsc::Receiver<Message>) {
-    let mut send_threads = false;
-    let mut proc = Proc::new();
+fn thread_main(
+    tx: mpsc::Sender<Message>,
+    rx: mpsc::Receiver<Message>,
+    mut source: impl SystemSource,
+    mut recorder: Option<Recorder>,
+    mut send_threads: bool,
+    warm_up: Duration,
+) {
+    let mut paused = false;
+    let mut details_pid = None;
+
+    let host = source.host_info();
+    log::info!("Sampling {} running {}", host.hostname, host.kernel_release);
 
+    // The CPU usage is calculated from the previous sample, so take one that isn't sent
+    // for the first one sent to already have usage
+    if let Err(err) = source.sample(send_threads) {
+        log::warn!("Failed to take the initial sample: {err}");
+    }
+    thread::sleep(warm_up);
+
+    // Messages don't cause an earlier sample, since the usage over a much shorter
+    // interval would be off
+    let mut next_sample = Instant::now();
     loop {
-        if let Ok(system) = proc.get_system(send_threads) {
-            if tx.send(Message::SysInfo(system)).is_err() {
-                break;
+        if !paused && Instant::now() >= next_sample {
+            next_sample = Instant::now() + INTERVAL;
+            match source.sample(send_threads) {
+                Ok(system) => {
+                    if let Some(recorder) = &mut recorder {
+                        if let Err(err) = recorder.record(&system) {
+                            log::error!("Failed to record sample: {err}");
+                        }
+                    }
+                    if tx.send(Message::SysInfo(system)).is_err()
+                        || !send_details(&tx, &source, details_pid)
+                    {
+                        break;
+                    }
+                }
+                Err(err) => {
+                    log::error!("Failed to read system information: {err}");
+                    if tx.send(Message::Error(err)).is_err() {
+                        break;
+                    }
+                }
             }
         }
 
-        if let Ok(Message::SendThreads(state)) = rx.recv_timeout(Duration::from_millis(1_500)) {
-            if send_threads != state {
+        let msg = if paused {
+            rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            rx.recv_timeout(next_sample.saturating_duration_since(Instant::now()))
+        };
+        match msg {
+            Ok(Message::SendThreads(state)) if send_threads != state => {
                 send_threads = state;
-                proc.reset_prev_cpus();
+                source.reset_prev_cpus();
+            }
+            Ok(Message::ReadFds(state)) => source.read_fds(state),
+            Ok(Message::ReadIo(state)) => source.read_io(state),
+            Ok(Message::ReadGpuMemory(state)) => source.read_gpu_memory(state),
+            Ok(Message::ReadSwap(state)) => source.read_swap(state),
+            Ok(Message::ReadCaps(state)) => source.read_caps(state),
+            Ok(Message::ReadEnviron(state)) => source.read_environ(state),
+            Ok(Message::WatchDetails(pid)) => {
+                details_pid = pid;
+                // Right away, instead of showing no details until the next sample
+                if !send_details(&tx, &source, details_pid) {
+                    break;
+                }
+            }
+            Ok(Message::Pause(state)) if paused != state => {
+                paused = state;
+                // The usage after resuming should cover the time since resuming, not the
+                // whole pause
+                if !paused {
+                    source.reset_baselines();
+                    next_sample = Instant::now();
+                }
             }
+            Ok(Message::Quit) | Err(RecvTimeoutError::Disconnected) => break,
+            _ => {}
+        }
+    }
+}
+
+/// Send the details of the watched process, if the source can read them. Returns whether
+/// the app is still listening.
+fn send_details(tx: &mpsc::Sender<Message>, source: &impl SystemSource, pid: Option<i32>) -> bool {
+    match pid.and_then(|pid| Some((pid, source.process_details(pid)?))) {
+        Some((pid, details)) => tx.send(Message::Details(pid, details)).is_ok(),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fell::{HostInfo, ProcessDetails, Result, System};
+
+    use super::*;
+
+    /// Counts the calls made to it instead of reading anything
+    #[derive(Default)]
+    struct MockSource {
+        samples: usize,
+        resets: usize,
+    }
+
+    impl SystemSource for MockSource {
+        fn sample(&mut self, _get_threads: bool) -> Result<System> {
+            self.samples += 1;
+
+            Ok(System {
+                uptime: Duration::from_secs(self.samples as u64),
+                ..System::default()
+            })
         }
+
+        fn host_info(&self) -> HostInfo {
+            HostInfo::default()
+        }
+
+        fn reset_prev_cpus(&mut self) {}
+
+        fn reset_baselines(&mut self) {
+            self.resets += 1;
+        }
+
+        fn process_details(&self, _pid: i32) -> Option<ProcessDetails> {
+            Some(ProcessDetails {
+                wchan: Some("do_wait".to_string()),
+                cwd: Err(io::ErrorKind::NotFound.into()),
+                exe: Err(io::ErrorKind::NotFound.into()),
+            })
+        }
+    }
+
+    #[test]
+    fn samples_come_from_the_source() {
+        let (tx, thread_rx) = mpsc::channel();
+        let (main_tx, rx) = mpsc::channel();
+        let handle = thread::spawn(move || {
+            let mut source = MockSource::default();
+            thread_main(tx, rx, &mut source, None, false, Duration::ZERO);
+            source
+        });
+
+        let Ok(Message::SysInfo(system)) = thread_rx.recv() else {
+            panic!("Expected a sample");
+        };
+        // The initial sample isn't sent
+        assert_eq!(system.uptime, Duration::from_secs(2));
+
+        main_tx.send(Message::Pause(true)).unwrap();
+        main_tx.send(Message::Pause(false)).unwrap();
+        main_tx.send(Message::Quit).unwrap();
+        let source = handle.join().unwrap();
+
+        assert_eq!(source.samples, 3);
+        assert_eq!(source.resets, 1);
+    }
+
+    #[test]
+    fn messages_do_not_cause_early_samples() {
+        let (tx, thread_rx) = mpsc::channel();
+        let (main_tx, rx) = mpsc::channel();
+        let handle = thread::spawn(move || {
+            let mut source = MockSource::default();
+            thread_main(tx, rx, &mut source, None, false, Duration::ZERO);
+            source
+        });
+
+        assert!(matches!(thread_rx.recv(), Ok(Message::SysInfo(_))));
+        // Like the toggles the app sends at startup
+        for message in [
+            Message::SendThreads(false),
+            Message::ReadFds(true),
+            Message::ReadIo(true),
+            Message::ReadSwap(true),
+        ] {
+            main_tx.send(message).unwrap();
+        }
+        main_tx.send(Message::Quit).unwrap();
+        let source = handle.join().unwrap();
+
+        // The initial sample and the first one sent, the next isn't due yet
+        assert_eq!(source.samples, 2);
+    }
+
+    #[test]
+    fn watched_details_are_sent() {
+        let (tx, thread_rx) = mpsc::channel();
+        let (main_tx, rx) = mpsc::channel();
+        let handle = thread::spawn(move || {
+            thread_main(tx, rx, MockSource::default(), None, false, Duration::ZERO);
+        });
+
+        assert!(matches!(thread_rx.recv(), Ok(Message::SysInfo(_))));
+        main_tx.send(Message::WatchDetails(Some(42))).unwrap();
+        let Ok(Message::Details(pid, details)) = thread_rx.recv() else {
+            panic!("Expected details");
+        };
+        main_tx.send(Message::Quit).unwrap();
+        handle.join().unwrap();
+
+        assert_eq!(pid, 42);
+        assert_eq!(details.wchan.as_deref(), Some("do_wait"));
     }
 }
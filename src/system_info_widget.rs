@@ -1,23 +1,82 @@
+use std::time::SystemTime;
+
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
     style::{Style, Styled, Stylize},
     text::Line,
-    widgets::{Paragraph, Widget},
+    widgets::{Paragraph, Sparkline, Widget},
 };
 
 use crate::{
-    proc::System,
-    utils::{human_bytes, human_duration},
+    history::History,
+    proc::{cgroup::ContainerRuntime, System},
+    theme::{severity_style, Theme},
+    utils::{human_bytes_fixed_unit, human_datetime, human_duration, MemoryUnit, Precision},
 };
 
+/// The highest values seen since fell started or the peaks were last reset
+#[derive(Debug, Default)]
+pub struct Peaks {
+    pub cpu: f32,
+    pub load: f32,
+    pub mem_used: usize,
+}
+
+impl Peaks {
+    pub fn update(&mut self, system: &System) {
+        if let Some(cpu_usage) = &system.cpu_usage {
+            self.cpu = self.cpu.max(cpu_usage.total);
+        }
+        self.load = self.load.max(system.load_avg.one);
+        self.mem_used = self.mem_used.max(system.mem_usage.mem_used());
+    }
+}
+
 pub struct SystemInfoWidget<'a> {
     current_data: &'a System,
+    theme: &'a Theme,
+    peaks: Option<&'a Peaks>,
+    load_history: Option<&'a History<f32>>,
+    memory_unit: MemoryUnit,
+    precision: Precision,
 }
 
 impl<'a> SystemInfoWidget<'a> {
-    pub fn new(data: &'a System) -> Self {
-        SystemInfoWidget { current_data: data }
+    pub fn new(data: &'a System, theme: &'a Theme) -> Self {
+        SystemInfoWidget {
+            current_data: data,
+            theme,
+            peaks: None,
+            load_history: None,
+            memory_unit: MemoryUnit::Auto,
+            precision: Precision::default(),
+        }
+    }
+
+    pub fn memory_unit(mut self, unit: MemoryUnit) -> Self {
+        self.memory_unit = unit;
+
+        self
+    }
+
+    pub fn precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+
+        self
+    }
+
+    pub fn peaks(mut self, peaks: Option<&'a Peaks>) -> Self {
+        self.peaks = peaks;
+
+        self
+    }
+
+    /// Graph the 1 minute load average after the current values, oldest value first
+    pub fn load_history(mut self, history: &'a History<f32>) -> Self {
+        self.load_history = Some(history);
+
+        self
     }
 }
 
@@ -26,78 +85,176 @@ impl<'a> Widget for &mut SystemInfoWidget<'a> {
     where
         Self: Sized,
     {
-        let average_cpu = if let Some(cpu_usage) = &self.current_data.cpu_usage {
-            if let Some(cpu_usage) = cpu_usage.first() {
-                *cpu_usage
-            } else {
-                0.0
-            }
+        let average_cpu = self
+            .current_data
+            .cpu_usage
+            .as_ref()
+            .map_or(0.0, |cpu_usage| cpu_usage.total);
+        let average_cpu_style = severity_style(average_cpu, self.theme).bold();
+        // A load equal to the number of cores is 100%
+        let num_cores = self
+            .current_data
+            .cpu_usage
+            .as_ref()
+            .map_or(0, |cpu_usage| cpu_usage.cores.len());
+        let load_style = if num_cores > 0 {
+            severity_style(
+                self.current_data.load_avg.one * 100.0 / num_cores as f32,
+                self.theme,
+            )
         } else {
-            0.0
+            Style::default()
         };
-        let average_cpu_style = if average_cpu > 75.0 {
-            Style::default().red().bold()
-        } else if average_cpu > 50.0 {
-            Style::default().yellow().bold()
-        } else {
-            Style::default().bold()
-        };
-        let info = vec![
-            Line::default().spans(vec![
-                "Uptime: ".into(),
-                human_duration(self.current_data.uptime).set_style(Style::default().bold()),
-            ]),
-            Line::default().spans(vec![
-                "Average CPU: ".into(),
-                format!("{:.1}%", average_cpu).set_style(average_cpu_style),
-            ]),
-            Line::default().spans(vec![
-                "Load average: ".into(),
-                format!(
-                    "{:.2} {:.2} {:.2}",
-                    self.current_data.load_avg.one,
-                    self.current_data.load_avg.five,
-                    self.current_data.load_avg.fifteen
-                )
-                .set_style(Style::default().bold()),
-            ]),
-            Line::default().spans(vec![
-                "Memory: ".into(),
-                format!(
-                    "{}/{}",
-                    human_bytes(self.current_data.mem_usage.mem_used(), false),
-                    human_bytes(self.current_data.mem_usage.mem_total, false)
-                )
-                .set_style(Style::default().bold()),
+        let mut cpu_line = vec![
+            "Average CPU: ".into(),
+            self.precision
+                .percent(average_cpu)
+                .set_style(average_cpu_style),
+        ];
+        let mut load_line = vec![
+            "Load average: ".into(),
+            format!(
+                "{:.2} {:.2} {:.2}",
+                self.current_data.load_avg.one,
+                self.current_data.load_avg.five,
+                self.current_data.load_avg.fifteen
+            )
+            .set_style(load_style.bold()),
+        ];
+        let bytes = |bytes| human_bytes_fixed_unit(bytes, self.memory_unit, false);
+        let mut memory_line = vec![
+            "Memory: ".into(),
+            format!(
+                "{}/{}",
+                bytes(self.current_data.mem_usage.mem_used()),
+                bytes(self.current_data.mem_usage.mem_total)
+            )
+            .set_style(Style::default().bold()),
+        ];
+        if let Some(peaks) = self.peaks {
+            cpu_line.push(format!(" (peak {})", self.precision.percent(peaks.cpu)).into());
+            load_line.push(format!(" (peak {:.2})", peaks.load).into());
+            memory_line.push(format!(" (peak {})", bytes(peaks.mem_used)).into());
+        }
+        // Swap is only noise on systems without any
+        if self.current_data.mem_usage.swap_total > 0 {
+            memory_line.extend([
                 " Swap: ".into(),
                 format!(
                     "{}/{}",
-                    human_bytes(self.current_data.mem_usage.swap_used(), false),
-                    human_bytes(self.current_data.mem_usage.swap_total, false)
+                    bytes(self.current_data.mem_usage.swap_used()),
+                    bytes(self.current_data.mem_usage.swap_total)
                 )
                 .set_style(Style::default().bold()),
-            ]),
-            Line::default().spans(vec![
-                "Tasks: ".set_style(Style::default().cyan()),
-                self.current_data
-                    .num_threads
-                    .tasks
-                    .to_string()
-                    .set_style(Style::default().cyan().bold()),
-                " Threads: ".into(),
+                " In/Out: ".into(),
                 self.current_data
-                    .num_threads
-                    .threads
-                    .to_string()
+                    .swap_rate
+                    .map(|rate| format!("{:.0}/{:.0}", rate.pages_in, rate.pages_out))
+                    .unwrap_or_else(|| "-/-".to_string())
                     .set_style(Style::default().bold()),
-                " Kernel Threads: ".set_style(Style::default().gray()),
-                self.current_data
-                    .num_threads
-                    .kernel_threads
-                    .to_string()
-                    .set_style(Style::default().gray().bold()),
+                " pages/s".into(),
+            ]);
+        }
+
+        let mut task_line = vec![
+            "Tasks: ".set_style(Style::default().cyan()),
+            self.current_data
+                .num_threads
+                .tasks
+                .to_string()
+                .set_style(Style::default().cyan().bold()),
+            " Threads: ".into(),
+            self.current_data
+                .num_threads
+                .threads
+                .to_string()
+                .set_style(Style::default().bold()),
+            " Kernel Threads: ".set_style(Style::default().gray()),
+            self.current_data
+                .num_threads
+                .kernel_threads
+                .to_string()
+                .set_style(Style::default().gray().bold()),
+        ];
+        let states = &self.current_data.num_states;
+        task_line.extend([
+            " (R:".into(),
+            states.running.to_string().bold(),
+            " S:".into(),
+            states.sleeping.to_string().bold(),
+            " D:".into(),
+            states.waiting.to_string().bold(),
+            " Z:".into(),
+            states.zombie.to_string().bold(),
+            " T:".into(),
+            states.stopped.to_string().bold(),
+            ")".into(),
+        ]);
+        let containers = &self.current_data.containers;
+        if !containers.is_empty() {
+            let mut runtimes: Vec<(ContainerRuntime, usize)> = Vec::new();
+            for container in containers {
+                match runtimes
+                    .iter_mut()
+                    .find(|(runtime, _)| *runtime == container.runtime)
+                {
+                    Some((_, count)) => *count += 1,
+                    None => runtimes.push((container.runtime, 1)),
+                }
+            }
+            let runtimes: Vec<String> = runtimes
+                .iter()
+                .map(|(runtime, count)| format!("{runtime} {count}"))
+                .collect();
+            task_line.extend([
+                " Containers: ".into(),
+                containers.len().to_string().bold(),
+                format!(" ({})", runtimes.join(", ")).into(),
+            ]);
+        }
+
+        let info = vec![
+            Line::default().spans(vec![
+                "Uptime: ".into(),
+                human_duration(self.current_data.uptime).set_style(Style::default().bold()),
+                " (up since ".into(),
+                SystemTime::now()
+                    .checked_sub(self.current_data.uptime)
+                    .map(human_datetime)
+                    .unwrap_or_default()
+                    .set_style(Style::default().bold()),
+                ")".into(),
             ]),
+            Line::default().spans(cpu_line),
+            Line::default().spans(load_line.clone()),
+            Line::default().spans(memory_line),
+            Line::default().spans(task_line),
         ];
         Paragraph::new(info).render(area, buf);
+
+        if let Some(history) = self.load_history.filter(|_| num_cores > 0) {
+            let x = Line::default().spans(load_line).width() as u16 + 1;
+            let width = area.width.saturating_sub(x).min(history.len() as u16);
+            let graph_area = Rect {
+                x: area.x + x,
+                y: area.y + 2,
+                width,
+                height: 1,
+            }
+            .intersection(area);
+
+            // Scaled so that a full bar is a load equal to the number of cores
+            let max = num_cores as u64 * 100;
+            let data: Vec<u64> = history
+                .iter()
+                .skip(history.len() - width as usize)
+                .map(|load| ((load * 100.0) as u64).min(max))
+                .collect();
+            Sparkline::default()
+                .data(&data)
+                .max(max)
+                .style(Style::default().green().patch(load_style))
+                .render(graph_area, buf);
+        }
     }
 }
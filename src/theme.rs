@@ -0,0 +1,300 @@
+use std::env;
+
+use ratatui::{
+    buffer::Buffer,
+    style::{Color, Style, Stylize},
+};
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+
+use crate::proc::sched::SchedPolicy;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    /// Percentages above this are shown as a warning
+    pub warning_threshold: f32,
+    /// Percentages above this are shown as critical
+    pub critical_threshold: f32,
+    /// Highlight processes whose resident memory exceeds this
+    pub memory_highlight: Option<MemoryThreshold>,
+    /// Use colors, otherwise only bold, dim and reversed text are used
+    pub color: bool,
+    /// Style of the selected row
+    pub selection: SelectionStyle,
+    /// Colors for processes matching a pattern, the first matching rule is used
+    pub highlight: Vec<HighlightRule>,
+    /// Background of every other row of the process list, to follow a row across
+    #[serde(deserialize_with = "deserialize_optional_color")]
+    pub stripe: Option<Color>,
+}
+
+/// Style of the selected row. Only what is set here replaces the style of the row, so
+/// e.g. only setting a background keeps the colors that tell processes apart.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct SelectionStyle {
+    #[serde(default, deserialize_with = "deserialize_optional_color")]
+    pub fg: Option<Color>,
+    #[serde(default, deserialize_with = "deserialize_optional_color")]
+    pub bg: Option<Color>,
+    #[serde(default)]
+    pub bold: bool,
+    #[serde(default)]
+    pub reversed: bool,
+}
+
+impl Default for SelectionStyle {
+    fn default() -> Self {
+        SelectionStyle {
+            fg: None,
+            bg: None,
+            bold: false,
+            reversed: true,
+        }
+    }
+}
+
+/// Draws the processes whose name or command matches `pattern` in `color`
+#[derive(Debug, Clone, Deserialize)]
+pub struct HighlightRule {
+    #[serde(deserialize_with = "deserialize_regex")]
+    pub pattern: Regex,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub color: Color,
+}
+
+/// Parse a color name like "blue", an index like "33" or a hex color like "#1e90ff"
+fn deserialize_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let input = String::deserialize(deserializer)?;
+    input
+        .parse()
+        .map_err(|_| serde::de::Error::custom(format!("invalid color '{input}'")))
+}
+
+fn deserialize_optional_color<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Color>, D::Error> {
+    deserialize_color(deserializer).map(Some)
+}
+
+fn deserialize_regex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+    let input = String::deserialize(deserializer)?;
+    Regex::new(&input).map_err(serde::de::Error::custom)
+}
+
+/// A resident memory limit, either in bytes or as a percentage of the total memory
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryThreshold {
+    Bytes(usize),
+    Percent(f32),
+}
+
+impl MemoryThreshold {
+    pub fn exceeded(&self, memory: usize, mem_total: usize) -> bool {
+        match *self {
+            MemoryThreshold::Bytes(bytes) => memory > bytes,
+            MemoryThreshold::Percent(percent) => {
+                mem_total > 0 && memory as f32 * 100.0 / mem_total as f32 > percent
+            }
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            warning_threshold: 50.0,
+            critical_threshold: 75.0,
+            memory_highlight: None,
+            color: true,
+            selection: SelectionStyle::default(),
+            highlight: Vec::new(),
+            stripe: None,
+        }
+    }
+}
+
+impl Theme {
+    /// Style of the selected row. Without colors the row is always reversed, since a
+    /// background color alone would be lost.
+    pub fn selection_style(&self) -> Style {
+        let selection = &self.selection;
+        if !self.color {
+            return Style::new().reversed();
+        }
+
+        let mut style = Style::new();
+        if let Some(fg) = selection.fg {
+            style = style.fg(fg);
+        }
+        if let Some(bg) = selection.bg {
+            style = style.bg(bg);
+        }
+        if selection.bold {
+            style = style.bold();
+        }
+        if selection.reversed {
+            style = style.reversed();
+        }
+
+        style
+    }
+}
+
+/// Whether colors have been turned off with the `NO_COLOR` environment variable, see
+/// <https://no-color.org>
+pub fn no_color_env() -> bool {
+    env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// Remove the colors from everything drawn to `buf`, keeping modifiers like bold
+pub fn strip_colors(buf: &mut Buffer) {
+    for cell in &mut buf.content {
+        cell.set_fg(Color::Reset).set_bg(Color::Reset);
+    }
+}
+
+/// Style for a usage percentage according to the thresholds in `theme`
+pub fn severity_style(percent: f32, theme: &Theme) -> Style {
+    if percent > theme.critical_threshold {
+        Style::default().red()
+    } else if percent > theme.warning_threshold {
+        Style::default().yellow()
+    } else {
+        Style::default()
+    }
+}
+
+/// Style for a process using `memory` bytes of resident memory out of `mem_total`, making
+/// the processes above the threshold in `theme` stand out
+pub fn memory_style(memory: usize, mem_total: usize, theme: &Theme) -> Style {
+    match theme.memory_highlight {
+        Some(threshold) if threshold.exceeded(memory, mem_total) => {
+            Style::default().on_red().bold()
+        }
+        _ => Style::default(),
+    }
+}
+
+/// Style for a process from the first highlight rule in `theme` that matches its name
+/// or command
+pub fn highlight_style(name: &str, cmdline: &str, theme: &Theme) -> Style {
+    theme
+        .highlight
+        .iter()
+        .find(|rule| rule.pattern.is_match(name) || rule.pattern.is_match(cmdline))
+        .map_or(Style::default(), |rule| Style::default().fg(rule.color))
+}
+
+/// Style for a process with the nice value `nice`, highlighting processes that have been
+/// given a higher priority and dimming those with a lower priority
+pub fn nice_style(nice: i32) -> Style {
+    match nice {
+        ..0 => Style::default().red().bold(),
+        0 => Style::default(),
+        _ => Style::default().dim(),
+    }
+}
+
+/// Style for a process scheduled with `policy`, making realtime processes stand out
+pub fn sched_style(policy: Option<SchedPolicy>) -> Style {
+    match policy {
+        Some(policy) if policy.is_realtime() => Style::default().magenta().bold(),
+        _ => Style::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn severity_boundaries() {
+        let theme = Theme::default();
+
+        assert_eq!(severity_style(0.0, &theme), Style::default());
+        assert_eq!(severity_style(50.0, &theme), Style::default());
+        assert_eq!(severity_style(50.1, &theme), Style::default().yellow());
+        assert_eq!(severity_style(75.0, &theme), Style::default().yellow());
+        assert_eq!(severity_style(75.1, &theme), Style::default().red());
+        assert_eq!(severity_style(100.0, &theme), Style::default().red());
+    }
+
+    #[test]
+    fn severity_custom_thresholds() {
+        let theme = Theme {
+            warning_threshold: 10.0,
+            critical_threshold: 20.0,
+            ..Theme::default()
+        };
+
+        assert_eq!(severity_style(15.0, &theme), Style::default().yellow());
+        assert_eq!(severity_style(25.0, &theme), Style::default().red());
+    }
+
+    #[test]
+    fn nice_levels() {
+        assert_eq!(nice_style(-20), Style::default().red().bold());
+        assert_eq!(nice_style(-1), Style::default().red().bold());
+        assert_eq!(nice_style(0), Style::default());
+        assert_eq!(nice_style(19), Style::default().dim());
+    }
+
+    #[test]
+    fn selection_only_sets_what_is_configured() {
+        let theme: Theme = toml::from_str("selection = { bg = \"blue\", bold = true }").unwrap();
+        assert_eq!(theme.selection_style(), Style::new().on_blue().bold());
+        assert_eq!(Theme::default().selection_style(), Style::new().reversed());
+        assert!(toml::from_str::<Theme>("selection = { bg = \"nope\" }").is_err());
+    }
+
+    #[test]
+    fn first_matching_highlight_rule_wins() {
+        let theme: Theme = toml::from_str(
+            r#"
+            [[highlight]]
+            pattern = "^postgres"
+            color = "green"
+
+            [[highlight]]
+            pattern = "sql"
+            color = "blue"
+            "#,
+        )
+        .unwrap();
+
+        let style = |name| highlight_style(name, "", &theme);
+        assert_eq!(style("postgresql"), Style::default().green());
+        assert_eq!(style("mysql"), Style::default().blue());
+        assert_eq!(style("bash"), Style::default());
+        assert_eq!(
+            highlight_style("bash", "/usr/bin/mysqld", &theme),
+            Style::default().blue()
+        );
+        assert!(
+            toml::from_str::<Theme>("highlight = [{ pattern = \"(\", color = \"red\" }]").is_err()
+        );
+    }
+
+    #[test]
+    fn memory_thresholds() {
+        let mut theme = Theme::default();
+        assert_eq!(memory_style(1 << 30, 1 << 32, &theme), Style::default());
+
+        theme.memory_highlight = Some(MemoryThreshold::Bytes(1 << 20));
+        assert_eq!(memory_style(1 << 20, 1 << 32, &theme), Style::default());
+        assert_eq!(
+            memory_style((1 << 20) + 1, 1 << 32, &theme),
+            Style::default().on_red().bold()
+        );
+
+        theme.memory_highlight = Some(MemoryThreshold::Percent(25.0));
+        assert_eq!(memory_style(1 << 30, 1 << 32, &theme), Style::default());
+        assert_eq!(
+            memory_style(1 << 31, 1 << 32, &theme),
+            Style::default().on_red().bold()
+        );
+        assert_eq!(memory_style(1 << 31, 0, &theme), Style::default());
+    }
+}
@@ -0,0 +1,159 @@
+use std::{fmt::Write, thread, time::Duration};
+
+use fell::{
+    proc::{
+        self,
+        process_info::{ProcessInfo, ProcessType},
+        System,
+    },
+    SystemSource,
+};
+
+use crate::{
+    column::Column,
+    process_list::{row_cells, NameMode, RowFormat},
+    sort_key::{Key, SortKey, SortSpec},
+    utils::{MemoryUnit, Precision, UserCache},
+};
+
+/// Time between the two samples CPU usage is calculated from, unless given with `-d`
+pub const DEFAULT_DELAY: Duration = Duration::from_secs(1);
+
+const COLUMNS: [Column; 6] = [
+    Column::Pid,
+    Column::User,
+    Column::Name,
+    Column::Cpu,
+    Column::Res,
+    Column::Command,
+];
+
+/// Take two samples `delay` apart and list the `count` processes using the most CPU and
+/// the most memory, as two plain text tables
+pub fn top_summary(
+    source: &mut impl SystemSource,
+    count: usize,
+    delay: Duration,
+    memory_unit: MemoryUnit,
+    precision: Precision,
+) -> proc::Result<String> {
+    source.sample(false)?;
+    thread::sleep(delay);
+    let system = source.sample(false)?;
+
+    Ok(format_summary(&system, count, memory_unit, precision))
+}
+
+fn format_summary(
+    system: &System,
+    count: usize,
+    memory_unit: MemoryUnit,
+    precision: Precision,
+) -> String {
+    let processes: Vec<&ProcessInfo> = system
+        .processes
+        .iter()
+        .filter(|p| p.process_type != ProcessType::KernelThread)
+        .collect();
+    let format = RowFormat {
+        columns: &COLUMNS,
+        name_mode: NameMode::Comm,
+        memory_unit,
+        precision,
+    };
+    let mut users = UserCache::default();
+
+    let mut out = String::new();
+    for key in [SortKey::Cpu, SortKey::Res] {
+        let sort = SortSpec::new(key);
+        let mut top = processes.clone();
+        top.sort_by(|a, b| sort.compare(a, b));
+        top.truncate(count);
+
+        let rows: Vec<Vec<String>> = top
+            .iter()
+            .map(|p| {
+                // Command lines can contain newlines, which would break up the table
+                row_cells(p, p.cpu_usage, "", &format, &mut users)
+                    .into_iter()
+                    .map(|cell| cell.replace(char::is_control, " "))
+                    .collect()
+            })
+            .collect();
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        let _ = writeln!(out, "Top {count} by {}", key.header());
+        write_table(&mut out, &rows);
+    }
+
+    out
+}
+
+/// Write `rows` below a header of [`COLUMNS`], with every column but the last padded to
+/// its widest cell
+fn write_table(out: &mut String, rows: &[Vec<String>]) {
+    let header: Vec<String> = COLUMNS.iter().map(|c| c.header().to_string()).collect();
+    let widths: Vec<usize> = (0..COLUMNS.len())
+        .map(|i| {
+            std::iter::once(&header)
+                .chain(rows)
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    for row in std::iter::once(&header).chain(rows) {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let _ = writeln!(out, "{}", line.trim_end());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn busiest_and_largest_processes() {
+        let process = |pid, name: &str, cpu_usage, memory| ProcessInfo {
+            pid,
+            tgid: pid,
+            name: name.to_string(),
+            cpu_usage: Some(cpu_usage),
+            memory,
+            cmdline: name.to_string(),
+            ..Default::default()
+        };
+        let system = System {
+            processes: vec![
+                process(1, "init", 0.5, 8 * 1024 * 1024),
+                process(2, "build", 90.0, 100 * 1024 * 1024),
+                process(3, "db", 5.0, 900 * 1024 * 1024),
+                ProcessInfo {
+                    process_type: ProcessType::KernelThread,
+                    ..process(4, "kworker", 99.0, 0)
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            format_summary(&system, 2, MemoryUnit::Auto, Precision::default()),
+            "Top 2 by CPU%\n\
+             PID     User Name  CPU%   Res      Command\n\
+             \x20     2      build  90.0%  100.00M build\n\
+             \x20     3      db      5.0%  900.00M db\n\
+             \n\
+             Top 2 by Res\n\
+             PID     User Name  CPU%   Res      Command\n\
+             \x20     3      db      5.0%  900.00M db\n\
+             \x20     2      build  90.0%  100.00M build\n"
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::proc::process_info::ProcessInfo;
+
+/// Arrange `processes` as a tree, each process followed by its children, together with
+/// the lines to draw in front of its name.
+///
+/// Siblings keep their order, so sorting the processes first sorts each level of the
+/// tree. Threads are shown as children of their process. Processes whose parent isn't
+/// among `processes`, e.g. because it is filtered out, are shown at the top level.
+pub fn arrange(processes: Vec<&ProcessInfo>) -> Vec<(&ProcessInfo, String)> {
+    let present: HashSet<i32> = processes.iter().map(|p| p.pid).collect();
+    let parent = |p: &ProcessInfo| {
+        let parent = if p.tgid != p.pid { p.tgid } else { p.ppid };
+        (parent != p.pid && present.contains(&parent)).then_some(parent)
+    };
+
+    let mut children: HashMap<i32, Vec<usize>> = HashMap::new();
+    let mut roots = Vec::new();
+    for (i, p) in processes.iter().enumerate() {
+        match parent(p) {
+            Some(parent) => children.entry(parent).or_default().push(i),
+            None => roots.push(i),
+        }
+    }
+
+    let mut rows = Vec::with_capacity(processes.len());
+    let mut visited = vec![false; processes.len()];
+    // Processes in a loop of parents aren't reachable from a root, so they start their
+    // own tree once all roots are done rather than not being shown at all
+    for root in roots.into_iter().chain(0..processes.len()) {
+        // Each entry is a process, the lines in front of its name and in front of the
+        // names of its children
+        let mut stack = vec![(root, String::new(), String::new())];
+        while let Some((i, prefix, indent)) = stack.pop() {
+            if visited[i] {
+                continue;
+            }
+            visited[i] = true;
+            rows.push((processes[i], prefix));
+
+            let Some(children) = children.get(&processes[i].pid) else {
+                continue;
+            };
+            let children: Vec<usize> = children.iter().copied().filter(|&c| !visited[c]).collect();
+            for (n, &child) in children.iter().enumerate().rev() {
+                let (branch, continuation) = if n == children.len() - 1 {
+                    ("└─ ", "   ")
+                } else {
+                    ("├─ ", "│  ")
+                };
+                stack.push((
+                    child,
+                    format!("{indent}{branch}"),
+                    format!("{indent}{continuation}"),
+                ));
+            }
+        }
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: i32, ppid: i32) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            tgid: pid,
+            ppid,
+            ..ProcessInfo::default()
+        }
+    }
+
+    #[test]
+    fn children_follow_their_parent() {
+        let processes = [
+            process(1, 0),
+            process(30, 20),
+            process(10, 1),
+            process(11, 10),
+            process(12, 1),
+            // Its parent exited, so it is shown at the top level
+            process(40, 35),
+        ];
+        let rows: Vec<(i32, String)> = arrange(processes.iter().collect())
+            .into_iter()
+            .map(|(p, prefix)| (p.pid, prefix))
+            .collect();
+
+        assert_eq!(
+            rows,
+            [
+                (1, "".to_string()),
+                (10, "├─ ".to_string()),
+                (11, "│  └─ ".to_string()),
+                (12, "└─ ".to_string()),
+                (30, "".to_string()),
+                (40, "".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn processes_in_a_loop_are_kept() {
+        let processes = [process(1, 2), process(2, 1)];
+
+        assert_eq!(arrange(processes.iter().collect()).len(), 2);
+    }
+}
@@ -1,10 +1,11 @@
-use std::io::{self, stdout, Stdout};
+use std::io::{self, stdout, Stdout, Write};
 
 use crossterm::{
-    execute,
+    execute, queue,
+    style::{Attribute, Print, SetAttribute, SetBackgroundColor, SetForegroundColor},
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use ratatui::{prelude::CrosstermBackend, Terminal};
+use ratatui::{buffer::Buffer, prelude::CrosstermBackend, style::Modifier, Terminal};
 
 pub type Tui = Terminal<CrosstermBackend<Stdout>>;
 
@@ -19,3 +20,83 @@ pub fn restore() -> io::Result<()> {
     disable_raw_mode()?;
     Ok(())
 }
+
+/// Print `frame` to the main screen, after [`restore`] has left the alternate screen, so
+/// that it stays in the scrollback like the output of any other command
+pub fn print_frame(frame: &Buffer) -> io::Result<()> {
+    let mut out = stdout().lock();
+    write_frame(&mut out, frame)?;
+    out.flush()
+}
+
+fn write_frame(out: &mut impl Write, frame: &Buffer) -> io::Result<()> {
+    const ATTRIBUTES: [(Modifier, Attribute); 6] = [
+        (Modifier::BOLD, Attribute::Bold),
+        (Modifier::DIM, Attribute::Dim),
+        (Modifier::ITALIC, Attribute::Italic),
+        (Modifier::UNDERLINED, Attribute::Underlined),
+        (Modifier::REVERSED, Attribute::Reverse),
+        (Modifier::CROSSED_OUT, Attribute::CrossedOut),
+    ];
+
+    let area = frame.area;
+    for y in area.top()..area.bottom() {
+        // Trailing blanks are left out so that a narrower terminal doesn't wrap them
+        let end = (area.left()..area.right())
+            .rev()
+            .find(|&x| frame[(x, y)].symbol() != " " || frame[(x, y)].bg != Default::default())
+            .map_or(area.left(), |x| x + 1);
+        let mut style = None;
+        for x in area.left()..end {
+            let cell = &frame[(x, y)];
+            // The cells covered by a wide character are empty or skipped
+            if cell.skip || cell.symbol().is_empty() {
+                continue;
+            }
+            if style != Some(cell.style()) {
+                queue!(
+                    out,
+                    SetAttribute(Attribute::Reset),
+                    SetForegroundColor(cell.fg.into()),
+                    SetBackgroundColor(cell.bg.into())
+                )?;
+                for (modifier, attribute) in ATTRIBUTES {
+                    if cell.modifier.contains(modifier) {
+                        queue!(out, SetAttribute(attribute))?;
+                    }
+                }
+                style = Some(cell.style());
+            }
+            queue!(out, Print(cell.symbol()))?;
+        }
+        queue!(out, SetAttribute(Attribute::Reset), Print("\n"))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{layout::Rect, style::Stylize, text::Line, widgets::Widget};
+
+    use super::*;
+
+    #[test]
+    fn frame_is_printed_line_by_line() {
+        let area = Rect::new(0, 0, 10, 2);
+        let mut frame = Buffer::empty(area);
+        Line::from(vec!["ab".into(), "c".bold()]).render(area, &mut frame);
+
+        let mut out = Vec::new();
+        write_frame(&mut out, &frame).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let lines: Vec<&str> = out.split('\n').collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("ab"));
+        assert!(lines[0].contains("\x1b[1mc"));
+        assert!(!lines[0].ends_with(' '));
+        // An empty line is only reset
+        assert_eq!(lines[1], "\x1b[0m");
+    }
+}
@@ -1,4 +1,13 @@
-use std::{ffi::CStr, mem, ptr, time::Duration};
+use std::{
+    collections::HashMap,
+    ffi::CStr,
+    io, mem, ptr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use clap::ValueEnum;
+use rustix::process::{self, Pid, Signal};
+use serde::Deserialize;
 
 pub fn get_username_from_uid(uid: u32) -> Option<String> {
     unsafe {
@@ -27,6 +36,19 @@ pub fn get_username_from_uid(uid: u32) -> Option<String> {
     }
 }
 
+#[derive(Debug, Default)]
+pub struct UserCache {
+    usernames: HashMap<u32, String>,
+}
+
+impl UserCache {
+    pub fn get(&mut self, uid: u32) -> &str {
+        self.usernames
+            .entry(uid)
+            .or_insert_with(|| get_username_from_uid(uid).unwrap_or_default())
+    }
+}
+
 pub fn human_duration(duration: Duration) -> String {
     let secs = duration.as_secs();
 
@@ -45,6 +67,30 @@ pub fn human_duration(duration: Duration) -> String {
     }
 }
 
+/// Format `time` in local time as "YYYY-MM-DD HH:MM"
+pub fn human_datetime(time: SystemTime) -> String {
+    let secs = match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as libc::time_t,
+        Err(_) => return String::default(),
+    };
+
+    unsafe {
+        let mut tm = mem::zeroed::<libc::tm>();
+        if libc::localtime_r(&secs, &mut tm).is_null() {
+            return String::default();
+        }
+
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}",
+            tm.tm_year + 1900,
+            tm.tm_mon + 1,
+            tm.tm_mday,
+            tm.tm_hour,
+            tm.tm_min
+        )
+    }
+}
+
 pub fn human_bytes(bytes: usize, fixed_width: bool) -> String {
     if bytes > 1024 {
         let (size, prefix) = if bytes > 1099511627776 {
@@ -68,3 +114,162 @@ pub fn human_bytes(bytes: usize, fixed_width: bool) -> String {
         format!("{bytes}")
     }
 }
+
+/// Unit to show memory in
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryUnit {
+    /// Scale each value to the largest unit that fits it
+    #[default]
+    Auto,
+    Kib,
+    Mib,
+    Gib,
+    Tib,
+}
+
+impl MemoryUnit {
+    /// Bytes per unit and the unit's suffix, or `None` for [`MemoryUnit::Auto`]
+    fn scale(&self) -> Option<(f64, char)> {
+        match self {
+            MemoryUnit::Auto => None,
+            MemoryUnit::Kib => Some((1024.0, 'k')),
+            MemoryUnit::Mib => Some((1048576.0, 'M')),
+            MemoryUnit::Gib => Some((1073741824.0, 'G')),
+            MemoryUnit::Tib => Some((1099511627776.0, 'T')),
+        }
+    }
+}
+
+/// Like [`human_bytes`], but always in `unit` so that values can be compared at a glance
+pub fn human_bytes_fixed_unit(bytes: usize, unit: MemoryUnit, fixed_width: bool) -> String {
+    let Some((bytes_per_unit, prefix)) = unit.scale() else {
+        return human_bytes(bytes, fixed_width);
+    };
+    let size = bytes as f64 / bytes_per_unit;
+
+    if fixed_width {
+        format!("{size:>7.2}{prefix}")
+    } else {
+        format!("{size:.2}{prefix}")
+    }
+}
+
+/// Number of decimals percentages are shown with, from 0 to 2
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "u8")]
+pub struct Precision(u8);
+
+impl Default for Precision {
+    fn default() -> Self {
+        Precision(1)
+    }
+}
+
+impl TryFrom<u8> for Precision {
+    type Error = String;
+
+    fn try_from(decimals: u8) -> Result<Self, Self::Error> {
+        if decimals <= 2 {
+            Ok(Precision(decimals))
+        } else {
+            Err(format!("at most 2 decimals, not {decimals}"))
+        }
+    }
+}
+
+impl Precision {
+    /// Width of the widest percentage, `100%` with the decimals
+    pub fn width(self) -> u16 {
+        match self.0 {
+            0 => 4,
+            decimals => 5 + decimals as u16,
+        }
+    }
+
+    pub fn percent(self, percent: f32) -> String {
+        let decimals = self.0 as usize;
+
+        format!("{percent:.decimals$}%")
+    }
+
+    /// Like [`Precision::percent`], but right aligned to [`Precision::width`] so that
+    /// percentages line up in columns
+    pub fn percent_fixed(self, percent: f32) -> String {
+        let width = self.width() as usize - 1;
+        let decimals = self.0 as usize;
+
+        format!("{percent:>width$.decimals$}%")
+    }
+}
+
+/// Width of a column of memory values up to `max_bytes` shown in `unit`. Values in a
+/// fixed unit grow past the 8 columns that values scaled on their own take.
+pub fn memory_width(max_bytes: usize, unit: MemoryUnit) -> u16 {
+    (human_bytes_fixed_unit(max_bytes, unit, true).len() as u16).max(8)
+}
+
+pub fn send_signal(pid: i32, signal: Signal) -> io::Result<()> {
+    let pid = Pid::from_raw(pid).ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?;
+
+    process::kill_process(pid, signal).map_err(io::Error::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_unit_does_not_scale() {
+        assert_eq!(human_bytes_fixed_unit(512, MemoryUnit::Mib, false), "0.00M");
+        assert_eq!(
+            human_bytes_fixed_unit(3 * 1073741824, MemoryUnit::Mib, true),
+            "3072.00M"
+        );
+        assert_eq!(
+            human_bytes_fixed_unit(1572864, MemoryUnit::Gib, true),
+            "   0.00G"
+        );
+        assert_eq!(
+            human_bytes_fixed_unit(1572864, MemoryUnit::Auto, true),
+            human_bytes(1572864, true)
+        );
+    }
+
+    #[test]
+    fn large_values_in_a_fixed_unit_fit() {
+        let ten_gib = 10 * 1073741824;
+
+        let mib = human_bytes_fixed_unit(ten_gib, MemoryUnit::Mib, true);
+        assert_eq!(mib, "10240.00M");
+        assert_eq!(memory_width(ten_gib, MemoryUnit::Mib), mib.len() as u16);
+
+        let kib = human_bytes_fixed_unit(ten_gib, MemoryUnit::Kib, true);
+        assert_eq!(kib, "10485760.00k");
+        assert_eq!(memory_width(ten_gib, MemoryUnit::Kib), kib.len() as u16);
+
+        assert_eq!(memory_width(ten_gib, MemoryUnit::Auto), 8);
+        assert_eq!(memory_width(0, MemoryUnit::Kib), 8);
+    }
+
+    #[test]
+    fn percentages_have_the_configured_decimals() {
+        let precision = |decimals| Precision::try_from(decimals).unwrap();
+
+        assert_eq!(precision(0).percent(12.5), "12%");
+        assert_eq!(precision(1).percent(12.34), "12.3%");
+        assert_eq!(precision(2).percent(12.345), "12.35%");
+        for decimals in 0..=2 {
+            let precision = precision(decimals);
+            assert_eq!(
+                precision.percent_fixed(100.0).len(),
+                precision.width() as usize
+            );
+            assert_eq!(
+                precision.percent_fixed(0.0).len(),
+                precision.width() as usize
+            );
+        }
+        assert!(Precision::try_from(3).is_err());
+    }
+}